@@ -0,0 +1,226 @@
+use crate::input::IoStrategy;
+use crate::jobs::{device_id, run_jobs, JobOptions};
+use crate::walk::{relative_name, walk_directory, WalkOptions, WalkedFile};
+use crate::{calculator, extra, shasum_base_dir, Compare, ComputeHash, IfMatch};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+// Files found by comparing a shasum file with the directory it lives in, by relative name.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    pub matched: Vec<String>,
+    pub modified: Vec<String>,
+    pub missing: Vec<String>,
+    // Listed file and the unlisted file with its contents.
+    pub moved: Vec<(String, String)>,
+    pub unlisted: Vec<String>,
+    // Files that could not be read, counted in none of the groups above.
+    pub errors: Vec<String>,
+}
+
+impl AuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.modified.is_empty()
+            && self.missing.is_empty()
+            && self.moved.is_empty()
+            && self.unlisted.is_empty()
+            && self.errors.is_empty()
+    }
+}
+
+enum Listed {
+    Missing,
+    Checked(Result<IfMatch, String>),
+}
+
+/*
+* Checks `tasks` (parsed from `shasum_file_path`) and walks the directory the shasum file lives
+  in for files it does not list. An unlisted file with the contents of a missing one is reported
+  as moved instead, so only missing files' hashes are looked for and only when some are missing.
+*/
+pub fn audit_shasum_file(
+    shasum_file_path: &str,
+    tasks: &[Compare],
+    walk_options: &WalkOptions,
+    job_options: &JobOptions,
+) -> Result<AuditReport, String> {
+    let base_dir = shasum_base_dir(shasum_file_path);
+    let walk = walk_directory(base_dir.to_string_lossy(), walk_options)?;
+    let name_of = |path: &str| relative_name(base_dir, Path::new(path)).unwrap_or(path.to_string());
+
+    let mut report = AuditReport {
+        errors: walk.errors,
+        ..AuditReport::default()
+    };
+    let mut listed = HashSet::from([name_of(shasum_file_path)]);
+    let mut missing = Vec::new();
+
+    // Results come back in task order, so they can be paired with the tasks again.
+    let mut results = Vec::with_capacity(tasks.len());
+    let job_options = JobOptions {
+        unordered: false,
+        ..*job_options
+    };
+    run_jobs(
+        tasks,
+        &job_options,
+        |task| device_id(&task.data),
+        |task| match fs::symlink_metadata(task.data.to_string()) {
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Listed::Missing,
+            _ => Listed::Checked(task.compute_from(&task.data.read_with(job_options.io))),
+        },
+        |_, result| results.push(result),
+    );
+
+    for (task, result) in tasks.iter().zip(results) {
+        let name = name_of(&task.data.to_string());
+        listed.insert(name.clone());
+        match result {
+            Listed::Missing => missing.push((name, task)),
+            Listed::Checked(Ok(IfMatch::Match(_))) => report.matched.push(name),
+            Listed::Checked(Ok(IfMatch::Failed(_))) => report.modified.push(name),
+            Listed::Checked(Err(error)) => report.errors.push(format!("{}: {}", name, error)),
+        }
+    }
+
+    let unlisted: Vec<WalkedFile> = walk
+        .files
+        .into_iter()
+        .filter(|file| !listed.contains(&file.name))
+        .collect();
+
+    for file in unlisted {
+        let moved_from = match find_moved(&file, &missing, job_options.io) {
+            Ok(moved_from) => moved_from,
+            Err(error) => {
+                report.errors.push(format!("{}: {}", file.name, error));
+                None
+            }
+        };
+        match moved_from {
+            Some(index) => {
+                let (name, _) = missing.remove(index);
+                report.moved.push((name, file.name));
+            }
+            None => report.unlisted.push(file.name),
+        }
+    }
+    report
+        .missing
+        .extend(missing.into_iter().map(|(name, _)| name));
+
+    Ok(report)
+}
+
+// Index of the missing file `file` has the contents of, hashing it once per set of options.
+fn find_moved(
+    file: &WalkedFile,
+    missing: &[(String, &Compare)],
+    io: IoStrategy,
+) -> Result<Option<usize>, String> {
+    let mut groups: Vec<(
+        &calculator::HashOptions,
+        Vec<calculator::SupportedAlgorithm>,
+    )> = Vec::new();
+    for (_, task) in missing {
+        let index = match groups
+            .iter()
+            .position(|(options, _)| *options == task.options())
+        {
+            Some(index) => index,
+            None => {
+                groups.push((task.options(), Vec::new()));
+                groups.len() - 1
+            }
+        };
+        for &algorithm in task.algorithms() {
+            let (options, algorithms) = &mut groups[index];
+            if !algorithms.contains(&algorithm)
+                && calculator::check_hash_options(algorithm, options).is_ok()
+            {
+                algorithms.push(algorithm);
+            }
+        }
+    }
+
+    for (options, algorithms) in groups {
+        if algorithms.is_empty() {
+            continue;
+        }
+        let hashes = file
+            .data
+            .read_with(io)
+            .compute_hashes_with_options(&algorithms, options)?;
+        let found = missing.iter().position(|(_, task)| {
+            task.options() == options
+                && algorithms.iter().zip(&hashes).any(|(algorithm, hash)| {
+                    task.algorithms().contains(algorithm)
+                        && extra::hash_matches(*algorithm, hash, task.expected_hash())
+                })
+        });
+        if found.is_some() {
+            return Ok(found);
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test_audit {
+    use super::*;
+    use crate::phase_shasum_file;
+
+    #[test]
+    fn test_audit_reports_every_group() {
+        let root = std::env::temp_dir().join(format!("ezcheck-audit-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("same.txt"), "same").unwrap();
+        fs::write(root.join("changed.txt"), "changed").unwrap();
+        fs::write(root.join("sub/moved.txt"), "moved").unwrap();
+        fs::write(root.join("new.txt"), "new").unwrap();
+
+        let hash = |text: &str| {
+            text.as_bytes()
+                .compute_hash(calculator::SupportedAlgorithm::SHA256)
+                .unwrap()
+        };
+        let manifest = root.join("SHA256SUMS");
+        fs::write(
+            &manifest,
+            format!(
+                "{}  same.txt\n{}  changed.txt\n{}  old.txt\n{}  gone.txt\n",
+                hash("same"),
+                hash("original"),
+                hash("moved"),
+                hash("gone")
+            ),
+        )
+        .unwrap();
+
+        let manifest = manifest.to_str().unwrap();
+        let tasks = phase_shasum_file(manifest, None).unwrap();
+        let report = audit_shasum_file(
+            manifest,
+            &tasks,
+            &WalkOptions::default(),
+            &JobOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            report,
+            AuditReport {
+                matched: vec![String::from("same.txt")],
+                modified: vec![String::from("changed.txt")],
+                missing: vec![String::from("gone.txt")],
+                moved: vec![(String::from("old.txt"), String::from("sub/moved.txt"))],
+                unlisted: vec![String::from("new.txt")],
+                errors: Vec::new(),
+            }
+        );
+        assert!(!report.is_clean());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,383 @@
+use crate::calculator::{
+    hash_calculator_with_options, Backend, HashOptions, SupportedAlgorithm, BUFFER_SIZE,
+};
+use crate::input::{read_file, IoStrategy};
+use std::fmt::Write;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_DATA_SIZE: usize = 16 * 1024 * 1024;
+pub const DEFAULT_ROUNDS: usize = 3;
+
+pub struct BenchResult {
+    pub algorithm: SupportedAlgorithm,
+    pub backend: Backend,
+    pub buffer_size: usize,
+    pub elapsed: Duration,
+}
+
+pub struct IoBenchResult {
+    pub algorithm: SupportedAlgorithm,
+    pub strategy: IoStrategy,
+    pub elapsed: Duration,
+}
+
+pub struct BenchReport {
+    pub data_size: usize,
+    pub rounds: usize,
+    pub cpu_features: Vec<(&'static str, bool)>,
+    pub results: Vec<BenchResult>,
+    // Filled by run_io_benchmarks, empty otherwise.
+    pub io_results: Vec<IoBenchResult>,
+}
+
+/*
+* Hardware acceleration ring, blake3 and crc32c pick up at runtime. SHA-NI and the ARMv8
+  SHA2 extensions matter most for SHA256, AVX2/NEON for BLAKE3 and xxHash.
+*/
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn cpu_features() -> Vec<(&'static str, bool)> {
+    vec![
+        ("SHA-NI", is_x86_feature_detected!("sha")),
+        ("AVX2", is_x86_feature_detected!("avx2")),
+        ("SSE4.2", is_x86_feature_detected!("sse4.2")),
+    ]
+}
+
+#[cfg(target_arch = "aarch64")]
+pub fn cpu_features() -> Vec<(&'static str, bool)> {
+    vec![
+        ("NEON", std::arch::is_aarch64_feature_detected!("neon")),
+        ("SHA2", std::arch::is_aarch64_feature_detected!("sha2")),
+        ("SHA3", std::arch::is_aarch64_feature_detected!("sha3")),
+        ("CRC", std::arch::is_aarch64_feature_detected!("crc")),
+    ]
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn cpu_features() -> Vec<(&'static str, bool)> {
+    Vec::new()
+}
+
+// Deterministic pseudo-random bytes (xorshift64), so no hasher can take a shortcut on zeros.
+pub fn synthetic_data(size: usize) -> Vec<u8> {
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut data = Vec::with_capacity(size + 8);
+    while data.len() < size {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        data.extend_from_slice(&state.to_le_bytes());
+    }
+    data.truncate(size);
+    data
+}
+
+fn megabytes_per_second(data_size: usize, elapsed: Duration) -> f64 {
+    data_size as f64 / 1_000_000.0 / elapsed.as_secs_f64().max(f64::EPSILON)
+}
+
+impl BenchResult {
+    pub fn megabytes_per_second(&self, data_size: usize) -> f64 {
+        megabytes_per_second(data_size, self.elapsed)
+    }
+}
+
+// Hashes `data` through a BufReader of `buffer_size` bytes, keeping the fastest of `rounds` runs.
+pub fn bench_algorithm(
+    algorithm: SupportedAlgorithm,
+    backend: Backend,
+    data: &[u8],
+    buffer_size: usize,
+    rounds: usize,
+) -> Result<BenchResult, String> {
+    let options = HashOptions {
+        backend,
+        ..HashOptions::default()
+    };
+    let mut fastest = Duration::MAX;
+
+    for _ in 0..rounds.max(1) {
+        let reader = BufReader::with_capacity(buffer_size, data);
+        let start = Instant::now();
+        hash_calculator_with_options(reader, algorithm, &options)
+            .map_err(|error| format!("Error: Error calculating hash: {}", error))?;
+        fastest = fastest.min(start.elapsed());
+    }
+
+    Ok(BenchResult {
+        algorithm,
+        backend,
+        buffer_size,
+        elapsed: fastest,
+    })
+}
+
+/*
+* Benchmarks every algorithm with every backend implementing it and every buffer size.
+  An empty `buffer_sizes` means BUFFER_SIZE only.
+*/
+pub fn run_benchmarks(
+    algorithms: &[SupportedAlgorithm],
+    data_size: usize,
+    buffer_sizes: &[usize],
+    rounds: usize,
+) -> Result<BenchReport, String> {
+    if buffer_sizes.contains(&0) {
+        return Err(String::from("Error: Buffer size must be greater than 0."));
+    }
+    let buffer_sizes = if buffer_sizes.is_empty() {
+        &[BUFFER_SIZE][..]
+    } else {
+        buffer_sizes
+    };
+
+    let data = synthetic_data(data_size);
+    let mut results = Vec::new();
+
+    for &algorithm in algorithms {
+        let mut backends = algorithm.available_backends();
+        if backends.is_empty() {
+            backends.push(Backend::Auto);
+        }
+
+        for backend in backends {
+            for &buffer_size in buffer_sizes {
+                results.push(bench_algorithm(
+                    algorithm,
+                    backend,
+                    &data,
+                    buffer_size,
+                    rounds,
+                )?);
+            }
+        }
+    }
+
+    Ok(BenchReport {
+        data_size,
+        rounds,
+        cpu_features: cpu_features(),
+        results,
+        io_results: Vec::new(),
+    })
+}
+
+/*
+* Hashes a temporary file of `data_size` bytes with each I/O strategy. The file is read once
+  before every run, so all strategies start from the page cache even though Buffered and Mmap
+  drop the pages they have hashed.
+*/
+pub fn run_io_benchmarks(
+    algorithms: &[SupportedAlgorithm],
+    data_size: usize,
+    rounds: usize,
+) -> Result<Vec<IoBenchResult>, String> {
+    let path = std::env::temp_dir().join(format!("ezcheck-bench-{}", std::process::id()));
+    fs::write(&path, synthetic_data(data_size))
+        .map_err(|error| format!("Error: Cannot write {}: {}", path.display(), error))?;
+
+    let results = bench_io_strategies(algorithms, &path, rounds);
+    let _ = fs::remove_file(&path);
+    results
+}
+
+fn bench_io_strategies(
+    algorithms: &[SupportedAlgorithm],
+    path: &std::path::Path,
+    rounds: usize,
+) -> Result<Vec<IoBenchResult>, String> {
+    let to_error = |error: io::Error| format!("Error: Cannot read {}: {}", path.display(), error);
+    let mut results = Vec::new();
+
+    for &algorithm in algorithms {
+        for strategy in [
+            IoStrategy::Standard,
+            IoStrategy::Buffered,
+            IoStrategy::Pipelined,
+            IoStrategy::Mmap,
+        ] {
+            let mut fastest = Duration::MAX;
+
+            for _ in 0..rounds.max(1) {
+                io::copy(&mut File::open(path).map_err(to_error)?, &mut io::sink())
+                    .map_err(to_error)?;
+
+                let start = Instant::now();
+                let file = File::open(path).map_err(to_error)?;
+                read_file(file, strategy, |reader| {
+                    hash_calculator_with_options(reader, algorithm, &HashOptions::default())
+                })
+                .and_then(|hash| hash)
+                .map_err(to_error)?;
+                fastest = fastest.min(start.elapsed());
+            }
+
+            results.push(IoBenchResult {
+                algorithm,
+                strategy,
+                elapsed: fastest,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+fn backend_name(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Auto => "-",
+        backend => backend.as_str(),
+    }
+}
+
+impl BenchReport {
+    pub fn to_table(&self) -> String {
+        let features = self
+            .cpu_features
+            .iter()
+            .map(|(name, detected)| format!("{} {}", name, if *detected { "yes" } else { "no" }))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut table = String::new();
+        let _ = writeln!(table, "CPU features: {}", features);
+        let _ = writeln!(
+            table,
+            "Data size: {} bytes, fastest of {} rounds",
+            self.data_size, self.rounds
+        );
+        let _ = writeln!(table);
+        let _ = writeln!(
+            table,
+            "{:<16} {:<8} {:>12} {:>12}",
+            "Algorithm", "Backend", "Buffer", "MB/s"
+        );
+        for result in &self.results {
+            let _ = writeln!(
+                table,
+                "{:<16} {:<8} {:>12} {:>12.1}",
+                result.algorithm.to_string(),
+                backend_name(result.backend),
+                result.buffer_size,
+                result.megabytes_per_second(self.data_size)
+            );
+        }
+
+        if !self.io_results.is_empty() {
+            let _ = writeln!(table);
+            let _ = writeln!(table, "{:<16} {:<8} {:>12}", "Algorithm", "I/O", "MB/s");
+            for result in &self.io_results {
+                let _ = writeln!(
+                    table,
+                    "{:<16} {:<8} {:>12.1}",
+                    result.algorithm.to_string(),
+                    result.strategy.as_str(),
+                    megabytes_per_second(self.data_size, result.elapsed)
+                );
+            }
+        }
+        table
+    }
+
+    // Algorithm and backend names are plain ASCII identifiers, no escaping is needed.
+    pub fn to_json(&self) -> String {
+        let features = self
+            .cpu_features
+            .iter()
+            .map(|(name, detected)| format!("\"{}\":{}", name, detected))
+            .collect::<Vec<_>>()
+            .join(",");
+        let results = self
+            .results
+            .iter()
+            .map(|result| {
+                format!(
+                    "{{\"algorithm\":\"{}\",\"backend\":\"{}\",\"buffer_size\":{},\"seconds\":{:.6},\"mb_per_s\":{:.1}}}",
+                    result.algorithm,
+                    result.backend,
+                    result.buffer_size,
+                    result.elapsed.as_secs_f64(),
+                    result.megabytes_per_second(self.data_size)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let io_results = self
+            .io_results
+            .iter()
+            .map(|result| {
+                format!(
+                    "{{\"algorithm\":\"{}\",\"io\":\"{}\",\"seconds\":{:.6},\"mb_per_s\":{:.1}}}",
+                    result.algorithm,
+                    result.strategy,
+                    result.elapsed.as_secs_f64(),
+                    megabytes_per_second(self.data_size, result.elapsed)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!(
+            "{{\"version\":\"{}\",\"data_size\":{},\"rounds\":{},\"cpu_features\":{{{}}},\"results\":[{}],\"io_results\":[{}]}}",
+            env!("CARGO_PKG_VERSION"),
+            self.data_size,
+            self.rounds,
+            features,
+            results,
+            io_results
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_bench {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_data() {
+        let data = synthetic_data(1001);
+        assert_eq!(data.len(), 1001);
+        assert_eq!(data, synthetic_data(1001));
+        assert!(data.iter().any(|byte| *byte != 0));
+    }
+
+    #[test]
+    fn test_run_benchmarks_covers_every_backend_and_buffer_size() {
+        let report = run_benchmarks(
+            &[SupportedAlgorithm::SHA256, SupportedAlgorithm::CRC32],
+            4096,
+            &[64, 1024],
+            1,
+        )
+        .unwrap();
+        let sha256_backends = SupportedAlgorithm::SHA256.available_backends().len();
+        assert_eq!(report.results.len(), (sha256_backends + 1) * 2);
+
+        let json = report.to_json();
+        assert!(json.starts_with('{') && json.ends_with('}'));
+        assert!(json.contains("\"algorithm\":\"CRC32\",\"backend\":\"auto\",\"buffer_size\":64"));
+        assert!(report.to_table().contains("CRC32"));
+    }
+
+    #[test]
+    fn test_run_io_benchmarks_covers_every_strategy() {
+        let results = run_io_benchmarks(&[SupportedAlgorithm::CRC32], 4096, 1).unwrap();
+        let strategies: Vec<IoStrategy> = results.iter().map(|result| result.strategy).collect();
+        assert_eq!(
+            strategies,
+            [
+                IoStrategy::Standard,
+                IoStrategy::Buffered,
+                IoStrategy::Pipelined,
+                IoStrategy::Mmap
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_benchmarks_rejects_empty_buffer() {
+        assert!(run_benchmarks(&[SupportedAlgorithm::CRC32], 16, &[0], 1).is_err());
+    }
+}
@@ -4,218 +4,841 @@
     feature = "mix_backend"
 )))]
 compile_error!("You must enable at least one of the features: 'hashes_backend', 'ring_backend' or 'mix_backend'.");
-#[cfg(any(
-    all(feature = "hashes_backend", feature = "ring_backend"),
-    all(feature = "hashes_backend", feature = "mix_backend"),
-    all(feature = "ring_backend", feature = "mix_backend"),
-    all(
-        feature = "hashes_backend",
-        feature = "ring_backend",
-        feature = "mix_backend"
-    )
-))]
-compile_error!(
-    "Only one of the features `hashes_backend`, `ring_backend`, or `mix_backend` can be enabled at a time."
-);
 
 use crate::extra::bytes_to_hex;
 use core::hash::Hasher;
 use std::fmt;
-use std::io::{BufRead, Error};
+use std::io::{BufRead, Error, ErrorKind};
 
-#[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
-use digest::DynDigest;
-#[cfg(any(feature = "ring_backend", feature = "mix_backend"))]
+#[cfg(feature = "hashes_backend")]
+use digest::block_api::BlockSizeUser;
+#[cfg(feature = "hashes_backend")]
+use digest::block_api::{Buffer, BufferKindUser, VariableOutputCore};
+#[cfg(feature = "hashes_backend")]
+use digest::{Digest, DynDigest, ExtendableOutput, Output};
+#[cfg(feature = "hashes_backend")]
+use hmac::{KeyInit, Mac, SimpleHmacReset};
+#[cfg(feature = "ring_backend")]
 use ring::digest::{Algorithm, Context, SHA256, SHA384, SHA512, SHA512_256};
-use twox_hash::{XxHash32, XxHash3_64, XxHash64};
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use twox_hash::{XxHash32, XxHash3_128, XxHash3_64, XxHash64};
+use zeroize::{Zeroize, Zeroizing};
 
 /*
 * Why we set BUFFER_SIZE as 8192
     https://doc.rust-lang.org/std/io/struct.BufReader.html#impl-BufReader%3CR%3E
-* Why we set allow(dead_code)
-    https://github.com/rust-lang/rust/issues/47133
+* Hashers are fed straight from the buffer of the BufRead they are given, so BUFFER_SIZE is
+  the capacity files are opened with and the chunk size the hashers see.
 */
-#[allow(dead_code)]
 pub const BUFFER_SIZE: usize = 8192;
 
+/*
+* BLAKE3 only spreads work across threads when it is given large slices at once,
+    https://docs.rs/blake3/latest/blake3/struct.Hasher.html#method.update_rayon
+*/
+const BLAKE3_BUFFER_SIZE: usize = 1024 * 1024;
+const BLAKE3_KEY_LEN: usize = 32;
+
+// A custom XXH3 secret must be at least XXH3_SECRET_SIZE_MIN bytes long, see xxhash.h.
+const XXH3_SECRET_MIN_LEN: usize = 136;
+
+#[cfg(feature = "hashes_backend")]
+const SHAKE128_DEFAULT_BITS: usize = 256;
+#[cfg(feature = "hashes_backend")]
+const SHAKE256_DEFAULT_BITS: usize = 512;
+#[cfg(feature = "hashes_backend")]
+const MAX_SHAKE_OUTPUT_BITS: usize = 65536;
+#[cfg(feature = "hashes_backend")]
+const BLAKE2B_MAX_BITS: usize = 512;
+#[cfg(feature = "hashes_backend")]
+const BLAKE2S_MAX_BITS: usize = 256;
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SupportedAlgorithm {
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     MD2,
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     MD4,
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     MD5,
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     SHA1,
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     SHA224,
     SHA256,
     SHA384,
     SHA512,
     SHA512_256,
+    #[cfg(feature = "hashes_backend")]
+    SHA3_224,
+    #[cfg(feature = "hashes_backend")]
+    SHA3_256,
+    #[cfg(feature = "hashes_backend")]
+    SHA3_384,
+    #[cfg(feature = "hashes_backend")]
+    SHA3_512,
+    // SHAKE and BLAKE2 output length in bits.
+    #[cfg(feature = "hashes_backend")]
+    SHAKE128(usize),
+    #[cfg(feature = "hashes_backend")]
+    SHAKE256(usize),
+    #[cfg(feature = "hashes_backend")]
+    BLAKE2B(usize),
+    #[cfg(feature = "hashes_backend")]
+    BLAKE2S(usize),
+    #[cfg(feature = "hashes_backend")]
+    SM3,
+    #[cfg(feature = "hashes_backend")]
+    STREEBOG256,
+    #[cfg(feature = "hashes_backend")]
+    STREEBOG512,
+    #[cfg(feature = "hashes_backend")]
+    RIPEMD160,
     XXHASH32,
     XXHASH64,
     XXHASH3_64,
+    XXHASH3_128,
+    BLAKE3,
+    CRC32,
+    CRC32C,
+    CRC64,
+    CKSUM,
+    BSDSUM,
+    SYSVSUM,
+}
+
+// Every algorithm compiled into this build, SHAKE and BLAKE2 at their default output length.
+pub const ALL_ALGORITHMS: &[SupportedAlgorithm] = &[
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::MD2,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::MD4,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::MD5,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::SHA1,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::SHA224,
+    SupportedAlgorithm::SHA256,
+    SupportedAlgorithm::SHA384,
+    SupportedAlgorithm::SHA512,
+    SupportedAlgorithm::SHA512_256,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::SHA3_224,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::SHA3_256,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::SHA3_384,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::SHA3_512,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::SHAKE128(SHAKE128_DEFAULT_BITS),
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::SHAKE256(SHAKE256_DEFAULT_BITS),
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::BLAKE2B(BLAKE2B_MAX_BITS),
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::BLAKE2S(BLAKE2S_MAX_BITS),
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::SM3,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::STREEBOG256,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::STREEBOG512,
+    #[cfg(feature = "hashes_backend")]
+    SupportedAlgorithm::RIPEMD160,
+    SupportedAlgorithm::XXHASH32,
+    SupportedAlgorithm::XXHASH64,
+    SupportedAlgorithm::XXHASH3_64,
+    SupportedAlgorithm::XXHASH3_128,
+    SupportedAlgorithm::BLAKE3,
+    SupportedAlgorithm::CRC32,
+    SupportedAlgorithm::CRC32C,
+    SupportedAlgorithm::CRC64,
+    SupportedAlgorithm::CKSUM,
+    SupportedAlgorithm::BSDSUM,
+    SupportedAlgorithm::SYSVSUM,
+];
+
+/*
+* Key material is wiped from memory when the options are dropped, and so are the copies the
+  hashers built from it keep: keyed BLAKE3 zeroizes itself, XXH3 hands its secret back to be
+  zeroed, and HMAC and keyed BLAKE2 states are wiped through Wiped.
+*/
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HashOptions {
+    pub key: Option<Zeroizing<Vec<u8>>>,
+    pub derive_key_context: Option<String>,
+    pub seed: Option<u64>,
+    pub secret: Option<Zeroizing<Vec<u8>>>,
+    pub hmac: bool,
+    pub backend: Backend,
+}
+
+/*
+* Chooses between ring and hashes for the algorithms both implement (SHA256, SHA384, SHA512
+  and SHA512_256). Auto prefers ring and falls back to hashes, e.g. for HMAC-SHA512/256.
+  xxHash, BLAKE3, CRC and sum have a single implementation and ignore the choice.
+*/
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    Auto,
+    Ring,
+    Hashes,
+}
+
+impl Backend {
+    pub fn from_input<S: AsRef<str>>(input: S) -> Result<Backend, String> {
+        let backend = match input.as_ref().trim().to_ascii_lowercase().as_str() {
+            "auto" => Backend::Auto,
+            "ring" => Backend::Ring,
+            "hashes" => Backend::Hashes,
+            other => {
+                return Err(format!(
+                    "Error: Unsupported backend: {} (expected auto, ring or hashes).",
+                    other
+                ))
+            }
+        };
+
+        if !backend.is_available() {
+            return Err(format!(
+                "Error: The {} backend is not compiled into this build.",
+                backend.as_str()
+            ));
+        }
+        Ok(backend)
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Backend::Auto => "auto",
+            Backend::Ring => "ring",
+            Backend::Hashes => "hashes",
+        }
+    }
+
+    pub const fn is_available(self) -> bool {
+        match self {
+            Backend::Auto => true,
+            Backend::Ring => cfg!(feature = "ring_backend"),
+            Backend::Hashes => cfg!(feature = "hashes_backend"),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum AlgorithmBackend {
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     Digest,
-    #[cfg(any(feature = "ring_backend", feature = "mix_backend"))]
+    #[cfg(feature = "ring_backend")]
     Ring,
     Xxhash,
+    Blake3,
+    Crc,
+    Sum,
+}
+
+impl AlgorithmBackend {
+    // The user facing backend this implementation belongs to, None for single-implementation ones.
+    const fn family(self) -> Option<Backend> {
+        match self {
+            #[cfg(feature = "hashes_backend")]
+            AlgorithmBackend::Digest => Some(Backend::Hashes),
+            #[cfg(feature = "ring_backend")]
+            AlgorithmBackend::Ring => Some(Backend::Ring),
+            AlgorithmBackend::Xxhash
+            | AlgorithmBackend::Blake3
+            | AlgorithmBackend::Crc
+            | AlgorithmBackend::Sum => None,
+        }
+    }
+}
+
+/*
+* Parses names such as `shake256/512` or `blake2b-256`, where the suffix is the
+  output length in bits. Returns None when `name` is not a member of `family` at all.
+*/
+#[cfg(feature = "hashes_backend")]
+fn parse_output_bits(
+    name: &str,
+    family: &str,
+    default_bits: usize,
+    max_bits: usize,
+) -> Option<Result<usize, String>> {
+    let suffix = name.strip_prefix(family)?;
+    if suffix.is_empty() {
+        return Some(Ok(default_bits));
+    }
+
+    let bits = suffix.strip_prefix(['/', '-', '_'])?;
+    if bits.is_empty() || !bits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(match bits.parse::<usize>() {
+        Ok(bits) if bits > 0 && bits % 8 == 0 && bits <= max_bits => Ok(bits),
+        _ => Err(format!(
+            "Error: Invalid output length for {}: {} (must be a multiple of 8 between 8 and {}).",
+            family, bits, max_bits
+        )),
+    })
 }
 
+// (name, default bits, maximum bits, constructor)
+#[cfg(feature = "hashes_backend")]
+type VariableOutputFamily = (&'static str, usize, usize, fn(usize) -> SupportedAlgorithm);
+
+#[cfg(feature = "hashes_backend")]
+const VARIABLE_OUTPUT_FAMILIES: [VariableOutputFamily; 4] = [
+    (
+        "shake128",
+        SHAKE128_DEFAULT_BITS,
+        MAX_SHAKE_OUTPUT_BITS,
+        SupportedAlgorithm::SHAKE128,
+    ),
+    (
+        "shake256",
+        SHAKE256_DEFAULT_BITS,
+        MAX_SHAKE_OUTPUT_BITS,
+        SupportedAlgorithm::SHAKE256,
+    ),
+    (
+        "blake2b",
+        BLAKE2B_MAX_BITS,
+        BLAKE2B_MAX_BITS,
+        SupportedAlgorithm::BLAKE2B,
+    ),
+    (
+        "blake2s",
+        BLAKE2S_MAX_BITS,
+        BLAKE2S_MAX_BITS,
+        SupportedAlgorithm::BLAKE2S,
+    ),
+];
+
 impl SupportedAlgorithm {
     pub fn from_input<S: AsRef<str>>(algorithm: S) -> Result<Self, String> {
         let algorithm = algorithm.as_ref().trim();
         let normalized = algorithm.to_ascii_lowercase();
 
+        #[cfg(feature = "hashes_backend")]
+        for (family, default_bits, max_bits, variant) in VARIABLE_OUTPUT_FAMILIES {
+            if let Some(bits) = parse_output_bits(&normalized, family, default_bits, max_bits) {
+                return bits.map(variant);
+            }
+        }
+
         match normalized.as_str() {
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             "md2" => Ok(Self::MD2),
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             "md4" => Ok(Self::MD4),
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             "md5" => Ok(Self::MD5),
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             "sha1" => Ok(Self::SHA1),
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             "sha224" => Ok(Self::SHA224),
             "sha256" => Ok(Self::SHA256),
             "sha384" => Ok(Self::SHA384),
             "sha512" => Ok(Self::SHA512),
             "sha512_256" | "sha512-256" | "sha512/256" => Ok(Self::SHA512_256),
+            #[cfg(feature = "hashes_backend")]
+            "sha3-224" | "sha3_224" | "sha3/224" => Ok(Self::SHA3_224),
+            #[cfg(feature = "hashes_backend")]
+            "sha3-256" | "sha3_256" | "sha3/256" => Ok(Self::SHA3_256),
+            #[cfg(feature = "hashes_backend")]
+            "sha3-384" | "sha3_384" | "sha3/384" => Ok(Self::SHA3_384),
+            #[cfg(feature = "hashes_backend")]
+            "sha3-512" | "sha3_512" | "sha3/512" => Ok(Self::SHA3_512),
+            #[cfg(feature = "hashes_backend")]
+            "blake2b512" | "b2" => Ok(Self::BLAKE2B(BLAKE2B_MAX_BITS)),
+            #[cfg(feature = "hashes_backend")]
+            "blake2s256" => Ok(Self::BLAKE2S(BLAKE2S_MAX_BITS)),
+            #[cfg(feature = "hashes_backend")]
+            "sm3" => Ok(Self::SM3),
+            #[cfg(feature = "hashes_backend")]
+            "streebog256" | "streebog-256" | "streebog_256" | "streebog/256" | "gost2012-256"
+            | "gost2012_256" => Ok(Self::STREEBOG256),
+            #[cfg(feature = "hashes_backend")]
+            "streebog512" | "streebog-512" | "streebog_512" | "streebog/512" | "gost2012-512"
+            | "gost2012_512" => Ok(Self::STREEBOG512),
+            #[cfg(feature = "hashes_backend")]
+            "ripemd160" | "ripemd-160" | "ripemd_160" | "rmd160" => Ok(Self::RIPEMD160),
             "xxhash32" | "xxh32" => Ok(Self::XXHASH32),
             "xxhash64" | "xxh64" => Ok(Self::XXHASH64),
             "xxh3" | "xxh3_64" | "xxh3-64" | "xxh3/64" | "xxhash3" | "xxhash3_64"
             | "xxhash3-64" | "xxhash3/64" => Ok(Self::XXHASH3_64),
+            "xxh128" | "xxh3_128" | "xxh3-128" | "xxh3/128" | "xxhash128" | "xxhash3_128"
+            | "xxhash3-128" | "xxhash3/128" => Ok(Self::XXHASH3_128),
+            "blake3" | "b3" => Ok(Self::BLAKE3),
+            "crc32" | "crc-32" => Ok(Self::CRC32),
+            "crc32c" | "crc-32c" => Ok(Self::CRC32C),
+            "crc64" | "crc-64" | "crc64-xz" | "crc64_xz" | "crc64/xz" => Ok(Self::CRC64),
+            "cksum" | "posix-cksum" => Ok(Self::CKSUM),
+            "bsd-sum" | "bsd_sum" | "bsdsum" | "sum-r" => Ok(Self::BSDSUM),
+            "sysv-sum" | "sysv_sum" | "sysvsum" | "sum-s" => Ok(Self::SYSVSUM),
             _ => Err(format!("Error: Unsupported algorithm: {}", algorithm)),
         }
     }
 
     pub const fn as_str(self) -> &'static str {
         match self {
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::MD2 => "MD2",
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::MD4 => "MD4",
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::MD5 => "MD5",
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::SHA1 => "SHA1",
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::SHA224 => "SHA224",
             SupportedAlgorithm::SHA256 => "SHA256",
             SupportedAlgorithm::SHA384 => "SHA384",
             SupportedAlgorithm::SHA512 => "SHA512",
             SupportedAlgorithm::SHA512_256 => "SHA512_256",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_224 => "SHA3_224",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_256 => "SHA3_256",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_384 => "SHA3_384",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_512 => "SHA3_512",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHAKE128(_) => "SHAKE128",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHAKE256(_) => "SHAKE256",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::BLAKE2B(_) => "BLAKE2B",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::BLAKE2S(_) => "BLAKE2S",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SM3 => "SM3",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::STREEBOG256 => "STREEBOG256",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::STREEBOG512 => "STREEBOG512",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::RIPEMD160 => "RIPEMD160",
             SupportedAlgorithm::XXHASH32 => "XXHASH32",
             SupportedAlgorithm::XXHASH64 => "XXHASH64",
             SupportedAlgorithm::XXHASH3_64 => "XXHASH3_64",
+            SupportedAlgorithm::XXHASH3_128 => "XXHASH3_128",
+            SupportedAlgorithm::BLAKE3 => "BLAKE3",
+            SupportedAlgorithm::CRC32 => "CRC32",
+            SupportedAlgorithm::CRC32C => "CRC32C",
+            SupportedAlgorithm::CRC64 => "CRC64",
+            SupportedAlgorithm::CKSUM => "CKSUM",
+            SupportedAlgorithm::BSDSUM => "BSDSUM",
+            SupportedAlgorithm::SYSVSUM => "SYSVSUM",
+        }
+    }
+
+    pub fn prefixed_hash_name(self) -> String {
+        match self.output_bits() {
+            Some(bits) => format!("{}/{}", self.prefixed_base_name(), bits),
+            None => self.prefixed_base_name().to_string(),
+        }
+    }
+
+    // Output length in bits for algorithms whose digest size is chosen by the user.
+    pub const fn output_bits(self) -> Option<usize> {
+        match self {
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHAKE128(bits)
+            | SupportedAlgorithm::SHAKE256(bits)
+            | SupportedAlgorithm::BLAKE2B(bits)
+            | SupportedAlgorithm::BLAKE2S(bits) => Some(bits),
+            _ => None,
+        }
+    }
+
+    // Name in BSD-style tagged lines, spelled as GNU coreutils and xxhsum write it where they can.
+    pub fn tag_name(self) -> String {
+        match self {
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::BLAKE2B(BLAKE2B_MAX_BITS) => String::from("BLAKE2b"),
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::BLAKE2B(bits) => format!("BLAKE2b-{}", bits),
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::BLAKE2S(BLAKE2S_MAX_BITS) => String::from("BLAKE2s"),
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::BLAKE2S(bits) => format!("BLAKE2s-{}", bits),
+            SupportedAlgorithm::XXHASH32 => String::from("XXH32"),
+            SupportedAlgorithm::XXHASH64 => String::from("XXH64"),
+            SupportedAlgorithm::XXHASH3_64 => String::from("XXH3"),
+            SupportedAlgorithm::XXHASH3_128 => String::from("XXH128"),
+            _ => self.prefixed_hash_name().to_ascii_uppercase(),
         }
     }
 
-    pub const fn prefixed_hash_name(self) -> &'static str {
+    // POSIX cksum and sum print a decimal checksum followed by a size column instead of hex.
+    pub const fn has_size_column(self) -> bool {
+        matches!(
+            self,
+            SupportedAlgorithm::CKSUM | SupportedAlgorithm::BSDSUM | SupportedAlgorithm::SYSVSUM
+        )
+    }
+
+    const fn prefixed_base_name(self) -> &'static str {
         match self {
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::MD2 => "md2",
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::MD4 => "md4",
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::MD5 => "md5",
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::SHA1 => "sha1",
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::SHA224 => "sha224",
             SupportedAlgorithm::SHA256 => "sha256",
             SupportedAlgorithm::SHA384 => "sha384",
             SupportedAlgorithm::SHA512 => "sha512",
             SupportedAlgorithm::SHA512_256 => "sha512/256",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_224 => "sha3-224",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_256 => "sha3-256",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_384 => "sha3-384",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_512 => "sha3-512",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHAKE128(_) => "shake128",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHAKE256(_) => "shake256",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::BLAKE2B(_) => "blake2b",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::BLAKE2S(_) => "blake2s",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SM3 => "sm3",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::STREEBOG256 => "streebog256",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::STREEBOG512 => "streebog512",
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::RIPEMD160 => "ripemd160",
             SupportedAlgorithm::XXHASH32 => "xxhash32",
             SupportedAlgorithm::XXHASH64 => "xxhash64",
             SupportedAlgorithm::XXHASH3_64 => "xxh3_64",
+            SupportedAlgorithm::XXHASH3_128 => "xxh3_128",
+            SupportedAlgorithm::BLAKE3 => "blake3",
+            SupportedAlgorithm::CRC32 => "crc32",
+            SupportedAlgorithm::CRC32C => "crc32c",
+            SupportedAlgorithm::CRC64 => "crc64",
+            SupportedAlgorithm::CKSUM => "cksum",
+            SupportedAlgorithm::BSDSUM => "bsd-sum",
+            SupportedAlgorithm::SYSVSUM => "sysv-sum",
         }
     }
 
-    const fn backend(self) -> AlgorithmBackend {
+    // Backends that can be requested for the algorithm, empty when it has a single implementation.
+    pub fn available_backends(self) -> Vec<Backend> {
+        self.backends()
+            .iter()
+            .filter_map(|backend| backend.family())
+            .collect()
+    }
+
+    // Implementations of the algorithm compiled into this build, in the order auto prefers them.
+    const fn backends(self) -> &'static [AlgorithmBackend] {
         match self {
             SupportedAlgorithm::XXHASH32
             | SupportedAlgorithm::XXHASH64
-            | SupportedAlgorithm::XXHASH3_64 => AlgorithmBackend::Xxhash,
-            #[cfg(any(feature = "ring_backend", feature = "mix_backend"))]
+            | SupportedAlgorithm::XXHASH3_64
+            | SupportedAlgorithm::XXHASH3_128 => &[AlgorithmBackend::Xxhash],
+            SupportedAlgorithm::BLAKE3 => &[AlgorithmBackend::Blake3],
+            SupportedAlgorithm::CRC32 | SupportedAlgorithm::CRC32C | SupportedAlgorithm::CRC64 => {
+                &[AlgorithmBackend::Crc]
+            }
+            SupportedAlgorithm::CKSUM
+            | SupportedAlgorithm::BSDSUM
+            | SupportedAlgorithm::SYSVSUM => &[AlgorithmBackend::Sum],
             SupportedAlgorithm::SHA256
             | SupportedAlgorithm::SHA384
             | SupportedAlgorithm::SHA512
-            | SupportedAlgorithm::SHA512_256 => AlgorithmBackend::Ring,
-            #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+            | SupportedAlgorithm::SHA512_256 => &[
+                #[cfg(feature = "ring_backend")]
+                AlgorithmBackend::Ring,
+                #[cfg(feature = "hashes_backend")]
+                AlgorithmBackend::Digest,
+            ],
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::MD2
             | SupportedAlgorithm::MD4
             | SupportedAlgorithm::MD5
             | SupportedAlgorithm::SHA1
-            | SupportedAlgorithm::SHA224 => AlgorithmBackend::Digest,
-            #[cfg(feature = "hashes_backend")]
-            SupportedAlgorithm::SHA256
-            | SupportedAlgorithm::SHA384
-            | SupportedAlgorithm::SHA512
-            | SupportedAlgorithm::SHA512_256 => AlgorithmBackend::Digest,
+            | SupportedAlgorithm::SHA224
+            | SupportedAlgorithm::SHA3_224
+            | SupportedAlgorithm::SHA3_256
+            | SupportedAlgorithm::SHA3_384
+            | SupportedAlgorithm::SHA3_512
+            | SupportedAlgorithm::SHAKE128(_)
+            | SupportedAlgorithm::SHAKE256(_)
+            | SupportedAlgorithm::BLAKE2B(_)
+            | SupportedAlgorithm::BLAKE2S(_)
+            | SupportedAlgorithm::SM3
+            | SupportedAlgorithm::STREEBOG256
+            | SupportedAlgorithm::STREEBOG512
+            | SupportedAlgorithm::RIPEMD160 => &[AlgorithmBackend::Digest],
         }
     }
 }
 
 impl fmt::Display for SupportedAlgorithm {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.as_str())
+        match self.output_bits() {
+            Some(bits) => write!(f, "{}/{}", self.as_str(), bits),
+            None => write!(f, "{}", self.as_str()),
+        }
+    }
+}
+
+/*
+* A hash in progress. Every algorithm is driven through this interface, so one pass over the
+  input can feed any number of hashers, see hash_calculator_multi.
+*/
+pub trait IncrementalHasher: Send {
+    fn update(&mut self, data: &[u8]);
+    fn finalize(self: Box<Self>) -> String;
+}
+
+// Any hasher state together with how to feed it and how to print its digest.
+struct StreamingHasher<S> {
+    state: S,
+    update: fn(&mut S, &[u8]),
+    finalize: fn(S) -> String,
+}
+
+impl<S: Send> IncrementalHasher for StreamingHasher<S> {
+    fn update(&mut self, data: &[u8]) {
+        (self.update)(&mut self.state, data);
+    }
+
+    fn finalize(self: Box<Self>) -> String {
+        (self.finalize)(self.state)
+    }
+}
+
+/*
+* Hasher state derived from a key, for types that do not zeroize themselves (ring's HMAC,
+  SimpleHmacReset, the BLAKE2 core). The value is dropped as usual and its bytes are overwritten
+  with zeroes afterwards; memory it points to is not.
+*/
+struct Wiped<T>(ManuallyDrop<T>);
+
+impl<T> Wiped<T> {
+    fn new(value: T) -> Wiped<T> {
+        Wiped(ManuallyDrop::new(value))
+    }
+}
+
+impl<T> Deref for Wiped<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Wiped<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> Drop for Wiped<T> {
+    fn drop(&mut self) {
+        // SAFETY: the value is dropped exactly once here and never read again, only overwritten.
+        unsafe {
+            ManuallyDrop::drop(&mut self.0);
+            zeroize::zeroize_flat_type(&mut self.0 as *mut ManuallyDrop<T>);
+        }
+    }
+}
+
+// An XXH3 hasher owns a copy of its secret, which is zeroed when the hasher is dropped.
+struct Xxh3Hasher<H> {
+    hasher: Option<H>,
+    into_secret: fn(H) -> Box<[u8]>,
+}
+
+impl<H> Xxh3Hasher<H> {
+    fn get(&mut self) -> &mut H {
+        self.hasher.as_mut().expect("XXH3 hasher used after drop")
+    }
+}
+
+impl<H> Drop for Xxh3Hasher<H> {
+    fn drop(&mut self) {
+        if let Some(hasher) = self.hasher.take() {
+            (self.into_secret)(hasher).zeroize();
+        }
     }
 }
 
+fn streaming<S: Send + 'static>(
+    state: S,
+    update: fn(&mut S, &[u8]),
+    finalize: fn(S) -> String,
+) -> Box<dyn IncrementalHasher> {
+    Box::new(StreamingHasher {
+        state,
+        update,
+        finalize,
+    })
+}
+
 fn consume_reader<R: BufRead, F>(reader: &mut R, mut update: F) -> Result<(), Error>
 where
     F: FnMut(&[u8]),
 {
-    let mut buffer = [0u8; BUFFER_SIZE];
-
     loop {
-        let read_bytes = reader.read(&mut buffer)?;
-        if read_bytes == 0 {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
             return Ok(());
         }
 
-        update(&buffer[..read_bytes]);
+        update(chunk);
+        let read_bytes = chunk.len();
+        reader.consume(read_bytes);
+    }
+}
+
+#[cfg(feature = "hashes_backend")]
+fn xof_hasher<H: ExtendableOutput + Default + Send + 'static>(
+    output_bits: usize,
+) -> Box<dyn IncrementalHasher> {
+    streaming(
+        (H::default(), output_bits / 8),
+        |(hasher, _), chunk| hasher.update(chunk),
+        |(hasher, output_size)| bytes_to_hex(&hasher.finalize_boxed(output_size)),
+    )
+}
+
+/*
+* blake2 only offers compile-time output sizes, so drive the variable-output core directly.
+  A keyed BLAKE2 hash processes the zero-padded key as its first block, see RFC 7693 section 3.3.
+*/
+#[cfg(feature = "hashes_backend")]
+fn blake2_hasher<C>(
+    new_core: fn(&[u8], &[u8], usize, usize) -> C,
+    output_bits: usize,
+    key: Option<&[u8]>,
+) -> Result<Box<dyn IncrementalHasher>, Error>
+where
+    C: VariableOutputCore + BufferKindUser + Send + 'static,
+    Buffer<C>: Send,
+{
+    let block_size = C::block_size();
+    let key = key.unwrap_or_default();
+    if key.len() > C::output_size() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("BLAKE2 key must be at most {} bytes", C::output_size()),
+        ));
+    }
+
+    let output_size = output_bits / 8;
+    let mut core = new_core(&[], &[], key.len(), output_size);
+    let mut buffer = Buffer::<C>::default();
+
+    if !key.is_empty() {
+        let mut key_block = Zeroizing::new(vec![0u8; block_size]);
+        key_block[..key.len()].copy_from_slice(key);
+        buffer.digest_blocks(&key_block, |blocks| core.update_blocks(blocks));
     }
+
+    Ok(streaming(
+        Wiped::new((core, buffer, output_size)),
+        |state, chunk| {
+            let (core, buffer, _) = &mut **state;
+            buffer.digest_blocks(chunk, |blocks| core.update_blocks(blocks))
+        },
+        |mut state| {
+            let (core, buffer, output_size) = &mut *state;
+            let mut output = Output::<C>::default();
+            core.finalize_variable_core(buffer, &mut output);
+            bytes_to_hex(&output[..*output_size])
+        },
+    ))
+}
+
+// ripemd is still on digest 0.10, so it cannot be boxed as a digest 0.11 DynDigest.
+#[cfg(feature = "hashes_backend")]
+fn ripemd160_hasher() -> Box<dyn IncrementalHasher> {
+    use ripemd::Digest as _;
+
+    streaming(
+        ripemd::Ripemd160::new(),
+        |hasher, chunk| hasher.update(chunk),
+        |hasher| bytes_to_hex(&hasher.finalize()),
+    )
 }
 
-#[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
-fn hash_with_digest<R: BufRead>(
-    reader: &mut R,
+#[cfg(feature = "hashes_backend")]
+fn digest_hasher(
     algorithm: SupportedAlgorithm,
-) -> Result<String, Error> {
-    let mut hasher: Box<dyn DynDigest> = match algorithm {
+    options: &HashOptions,
+) -> Result<Box<dyn IncrementalHasher>, Error> {
+    let hasher: Box<dyn DynDigest + Send> = match algorithm {
+        SupportedAlgorithm::BLAKE2B(bits) => {
+            return blake2_hasher(
+                blake2::Blake2bVarCore::new_with_params,
+                bits,
+                options.key.as_deref().map(Vec::as_slice),
+            )
+        }
+        SupportedAlgorithm::BLAKE2S(bits) => {
+            return blake2_hasher(
+                blake2::Blake2sVarCore::new_with_params,
+                bits,
+                options.key.as_deref().map(Vec::as_slice),
+            )
+        }
+        SupportedAlgorithm::SHAKE128(bits) => return Ok(xof_hasher::<sha3::Shake128>(bits)),
+        SupportedAlgorithm::SHAKE256(bits) => return Ok(xof_hasher::<sha3::Shake256>(bits)),
         SupportedAlgorithm::MD2 => Box::new(md2::Md2::default()),
         SupportedAlgorithm::MD4 => Box::new(md4::Md4::default()),
         SupportedAlgorithm::MD5 => Box::new(md5::Md5::default()),
         SupportedAlgorithm::SHA1 => Box::new(sha1::Sha1::default()),
         SupportedAlgorithm::SHA224 => Box::new(sha2::Sha224::default()),
-        #[cfg(feature = "hashes_backend")]
+        SupportedAlgorithm::SHA3_224 => Box::new(sha3::Sha3_224::default()),
+        SupportedAlgorithm::SHA3_256 => Box::new(sha3::Sha3_256::default()),
+        SupportedAlgorithm::SHA3_384 => Box::new(sha3::Sha3_384::default()),
+        SupportedAlgorithm::SHA3_512 => Box::new(sha3::Sha3_512::default()),
+        SupportedAlgorithm::SM3 => Box::new(sm3::Sm3::default()),
+        SupportedAlgorithm::STREEBOG256 => Box::new(streebog::Streebog256::default()),
+        SupportedAlgorithm::STREEBOG512 => Box::new(streebog::Streebog512::default()),
+        SupportedAlgorithm::RIPEMD160 => return Ok(ripemd160_hasher()),
         SupportedAlgorithm::SHA256 => Box::new(sha2::Sha256::default()),
-        #[cfg(feature = "hashes_backend")]
         SupportedAlgorithm::SHA384 => Box::new(sha2::Sha384::default()),
-        #[cfg(feature = "hashes_backend")]
         SupportedAlgorithm::SHA512 => Box::new(sha2::Sha512::default()),
-        #[cfg(feature = "hashes_backend")]
         SupportedAlgorithm::SHA512_256 => Box::new(sha2::Sha512_256::default()),
         _ => unreachable!("non-digest algorithms are handled separately"),
     };
 
-    consume_reader(reader, |chunk| hasher.update(chunk))?;
-
-    let digest = hasher.finalize_reset();
-    Ok(bytes_to_hex(digest.as_ref()))
+    Ok(streaming(
+        hasher,
+        |hasher, chunk| hasher.update(chunk),
+        |mut hasher| bytes_to_hex(&hasher.finalize_reset()),
+    ))
 }
 
-#[cfg(any(feature = "ring_backend", feature = "mix_backend"))]
+#[cfg(feature = "ring_backend")]
 fn ring_algorithm(algorithm: SupportedAlgorithm) -> &'static Algorithm {
     match algorithm {
         SupportedAlgorithm::SHA256 => &SHA256,
@@ -226,91 +849,890 @@ fn ring_algorithm(algorithm: SupportedAlgorithm) -> &'static Algorithm {
     }
 }
 
-#[cfg(any(feature = "ring_backend", feature = "mix_backend"))]
-fn hash_with_ring<R: BufRead>(
-    reader: &mut R,
-    algorithm: SupportedAlgorithm,
-) -> Result<String, Error> {
-    let mut hasher = Context::new(ring_algorithm(algorithm));
-    consume_reader(reader, |chunk| hasher.update(chunk))?;
+#[cfg(feature = "ring_backend")]
+fn ring_hasher(algorithm: SupportedAlgorithm) -> Box<dyn IncrementalHasher> {
+    streaming(
+        Context::new(ring_algorithm(algorithm)),
+        |hasher, chunk| hasher.update(chunk),
+        |hasher| bytes_to_hex(hasher.finish().as_ref()),
+    )
+}
 
-    let digest = hasher.finish();
-    Ok(bytes_to_hex(digest.as_ref()))
+// The rejected copy of the secret comes back with the error and is zeroed before it is dropped.
+fn xxhash3_secret_error(error: twox_hash::xxhash3_64::SecretTooShortError<Box<[u8]>>) -> Error {
+    error.into_secret().zeroize();
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("XXH3 secret must be at least {XXH3_SECRET_MIN_LEN} bytes"),
+    )
 }
 
-fn hash_with_xxhash<R: BufRead>(
-    reader: &mut R,
+fn xxhash_hasher(
     algorithm: SupportedAlgorithm,
-) -> Result<String, Error> {
-    let mut hasher: Box<dyn Hasher> = match algorithm {
-        SupportedAlgorithm::XXHASH32 => Box::new(XxHash32::with_seed(0)),
-        SupportedAlgorithm::XXHASH64 => Box::new(XxHash64::with_seed(0)),
-        SupportedAlgorithm::XXHASH3_64 => Box::new(XxHash3_64::with_seed(0)),
-        _ => unreachable!("non-xxhash algorithms are handled separately"),
-    };
+    options: &HashOptions,
+) -> Result<Box<dyn IncrementalHasher>, Error> {
+    let seed = options.seed.unwrap_or(0);
 
-    consume_reader(reader, |chunk| hasher.write(chunk))?;
-
-    let hash = hasher.finish();
-    Ok(match algorithm {
-        SupportedAlgorithm::XXHASH32 => format!("{hash:08x}"),
-        SupportedAlgorithm::XXHASH64 => format!("{hash:016x}"),
-        SupportedAlgorithm::XXHASH3_64 => format!("XXH3_{hash:016x}"),
+    match algorithm {
+        SupportedAlgorithm::XXHASH32 => {
+            let seed = u32::try_from(seed).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    "XXHASH32 seed must be at most 4294967295",
+                )
+            })?;
+            Ok(streaming(
+                XxHash32::with_seed(seed),
+                |hasher, chunk| hasher.write(chunk),
+                |hasher| format!("{:08x}", hasher.finish()),
+            ))
+        }
+        SupportedAlgorithm::XXHASH64 => Ok(streaming(
+            XxHash64::with_seed(seed),
+            |hasher, chunk| hasher.write(chunk),
+            |hasher| format!("{:016x}", hasher.finish()),
+        )),
+        SupportedAlgorithm::XXHASH3_64 => {
+            let hasher = match &options.secret {
+                Some(secret) => XxHash3_64::with_seed_and_secret(seed, secret.as_slice())
+                    .map_err(xxhash3_secret_error)?,
+                None => XxHash3_64::with_seed(seed),
+            };
+            Ok(streaming(
+                Xxh3Hasher {
+                    hasher: Some(hasher),
+                    into_secret: XxHash3_64::into_secret,
+                },
+                |hasher, chunk| hasher.get().write(chunk),
+                |mut hasher| format!("XXH3_{:016x}", hasher.get().finish()),
+            ))
+        }
+        SupportedAlgorithm::XXHASH3_128 => {
+            let hasher = match &options.secret {
+                Some(secret) => XxHash3_128::with_seed_and_secret(seed, secret.as_slice())
+                    .map_err(xxhash3_secret_error)?,
+                None => XxHash3_128::with_seed(seed),
+            };
+            Ok(streaming(
+                Xxh3Hasher {
+                    hasher: Some(hasher),
+                    into_secret: XxHash3_128::into_secret,
+                },
+                |hasher, chunk| hasher.get().write(chunk),
+                |mut hasher| format!("XXH3_{:032x}", hasher.get().finish_128()),
+            ))
+        }
         _ => unreachable!("non-xxhash algorithms are handled separately"),
-    })
+    }
 }
 
-pub fn hash_calculator<R: BufRead>(
-    mut reader: R,
-    algorithm: SupportedAlgorithm,
-) -> Result<String, Error> {
-    match algorithm.backend() {
-        AlgorithmBackend::Xxhash => hash_with_xxhash(&mut reader, algorithm),
-        #[cfg(any(feature = "ring_backend", feature = "mix_backend"))]
-        AlgorithmBackend::Ring => hash_with_ring(&mut reader, algorithm),
-        #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
-        AlgorithmBackend::Digest => hash_with_digest(&mut reader, algorithm),
+// CRC-64/XZ, the ECMA-182 polynomial in reflected form as used by xz and Go's crc64.ECMA.
+static CRC64_XZ: crc::Crc<u64> = crc::Crc::<u64>::new(&crc::CRC_64_XZ);
+
+fn crc_hasher(algorithm: SupportedAlgorithm) -> Box<dyn IncrementalHasher> {
+    match algorithm {
+        SupportedAlgorithm::CRC32 => streaming(
+            crc32fast::Hasher::new(),
+            |hasher, chunk| hasher.update(chunk),
+            |hasher| format!("{:08x}", hasher.finalize()),
+        ),
+        SupportedAlgorithm::CRC32C => streaming(
+            0,
+            |crc, chunk| *crc = crc32c::crc32c_append(*crc, chunk),
+            |crc| format!("{crc:08x}"),
+        ),
+        SupportedAlgorithm::CRC64 => streaming(
+            CRC64_XZ.digest(),
+            |digest, chunk| digest.update(chunk),
+            |digest| format!("{:016x}", digest.finalize()),
+        ),
+        _ => unreachable!("non-crc algorithms are handled separately"),
     }
 }
 
-#[cfg(test)]
-mod test_calculator {
-    use super::*;
-    use std::fs::File;
-    use std::io::BufReader;
+static CKSUM: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_CKSUM);
 
-    const TEST_WORD: &[u8; 16] = b"Veni, vidi, vici";
+/*
+* Output mirrors the tools byte for byte (without the file name):
+    cksum:   "<crc> <bytes>"
+    sum -r:  "%05d %5d", checksum and 1024-byte blocks
+    sum -s:  "%d %d", checksum and 512-byte blocks
+*/
+fn sum_hasher(algorithm: SupportedAlgorithm) -> Box<dyn IncrementalHasher> {
+    match algorithm {
+        SupportedAlgorithm::CKSUM => streaming(
+            (CKSUM.digest(), 0u64),
+            |(digest, length), chunk| {
+                *length += chunk.len() as u64;
+                digest.update(chunk);
+            },
+            |(mut digest, length)| {
+                // POSIX appends the length, least significant byte first, without trailing zeros.
+                let mut remaining = length;
+                while remaining > 0 {
+                    digest.update(&[remaining as u8]);
+                    remaining >>= 8;
+                }
 
-    #[test]
-    fn test_xxhash32() {
-        let reader = BufReader::new(&TEST_WORD[..]);
-        assert_eq!(
-            hash_calculator(reader, SupportedAlgorithm::XXHASH32).unwrap(),
-            "0163d3a2"
-        );
+                format!("{} {}", digest.finalize(), length)
+            },
+        ),
+        SupportedAlgorithm::BSDSUM => streaming(
+            (0u16, 0u64),
+            |(checksum, length), chunk| {
+                *length += chunk.len() as u64;
+                for &byte in chunk {
+                    *checksum = checksum.rotate_right(1).wrapping_add(u16::from(byte));
+                }
+            },
+            |(checksum, length)| format!("{:05} {:5}", checksum, length.div_ceil(1024)),
+        ),
+        SupportedAlgorithm::SYSVSUM => streaming(
+            (0u32, 0u64),
+            |(total, length), chunk| {
+                *length += chunk.len() as u64;
+                for &byte in chunk {
+                    *total = total.wrapping_add(u32::from(byte));
+                }
+            },
+            |(total, length)| {
+                let folded = (total & 0xffff) + (total >> 16);
+                let checksum = (folded & 0xffff) + (folded >> 16);
+                format!("{} {}", checksum, length.div_ceil(512))
+            },
+        ),
+        _ => unreachable!("non-sum algorithms are handled separately"),
     }
+}
 
-    #[test]
-    fn test_xxhash64() {
-        let reader = BufReader::new(&TEST_WORD[..]);
-        assert_eq!(
-            hash_calculator(reader, SupportedAlgorithm::XXHASH64).unwrap(),
-            "4a34911ba20e6c30"
-        );
+fn new_blake3(options: &HashOptions) -> Result<blake3::Hasher, Error> {
+    match (&options.key, &options.derive_key_context) {
+        (Some(_), Some(_)) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "BLAKE3 keyed mode and derive-key mode cannot be used together",
+        )),
+        (Some(key), None) => {
+            let key: Zeroizing<[u8; BLAKE3_KEY_LEN]> =
+                Zeroizing::new(key.as_slice().try_into().map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("BLAKE3 key must be exactly {BLAKE3_KEY_LEN} bytes"),
+                    )
+                })?);
+            Ok(blake3::Hasher::new_keyed(&key))
+        }
+        (None, Some(context)) => Ok(blake3::Hasher::new_derive_key(context)),
+        (None, None) => Ok(blake3::Hasher::new()),
     }
+}
 
-    #[test]
-    fn test_xxhash3_64() {
-        let reader = BufReader::new(&TEST_WORD[..]);
-        assert_eq!(
-            hash_calculator(reader, SupportedAlgorithm::XXHASH3_64).unwrap(),
-            "XXH3_802c0db623389036"
-        );
-    }
+// Small chunks are collected into BLAKE3_BUFFER_SIZE slices, which update_rayon can split up.
+fn blake3_hasher(options: &HashOptions) -> Result<Box<dyn IncrementalHasher>, Error> {
+    // A keyed hasher holds the key words, it is zeroized when dropped.
+    Ok(streaming(
+        (
+            Zeroizing::new(new_blake3(options)?),
+            Vec::with_capacity(BLAKE3_BUFFER_SIZE),
+        ),
+        |(hasher, buffer), chunk| {
+            if buffer.is_empty() && chunk.len() >= BLAKE3_BUFFER_SIZE {
+                hasher.update_rayon(chunk);
+                return;
+            }
 
-    #[test]
-    fn test_supported_algorithm_from_input_accepts_case_insensitive_aliases() {
-        assert_eq!(
+            buffer.extend_from_slice(chunk);
+            if buffer.len() >= BLAKE3_BUFFER_SIZE {
+                hasher.update_rayon(buffer);
+                buffer.clear();
+            }
+        },
+        |(mut hasher, buffer)| {
+            hasher.update_rayon(&buffer);
+            bytes_to_hex(hasher.finalize().as_bytes())
+        },
+    ))
+}
+
+#[cfg(feature = "hashes_backend")]
+fn hmac_with_digest<D>(key: &[u8]) -> Result<Box<dyn IncrementalHasher>, Error>
+where
+    D: Digest + digest::FixedOutputReset + BlockSizeUser + Send + 'static,
+{
+    /*
+    * SimpleHmacReset accepts keys of any length, hashing those longer than a block. It is
+      finalized in place, so the padded keys it holds are wiped with it.
+    */
+    let mac = <SimpleHmacReset<D> as KeyInit>::new_from_slice(key)
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "Invalid HMAC key"))?;
+
+    Ok(streaming(
+        Wiped::new(mac),
+        |mac, chunk| mac.update(chunk),
+        |mut mac| bytes_to_hex(&mac.finalize_reset().into_bytes()),
+    ))
+}
+
+// ripemd implements the older digest traits, so its HMAC comes from the matching hmac release.
+#[cfg(feature = "hashes_backend")]
+fn ripemd160_hmac_hasher(key: &[u8]) -> Result<Box<dyn IncrementalHasher>, Error> {
+    use hmac_digest010::{Mac, SimpleHmac};
+
+    let mac = <SimpleHmac<ripemd::Ripemd160> as Mac>::new_from_slice(key)
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "Invalid HMAC key"))?;
+
+    Ok(streaming(
+        Wiped::new(mac),
+        |mac, chunk| mac.update(chunk),
+        |mut mac| bytes_to_hex(&mac.finalize_reset().into_bytes()),
+    ))
+}
+
+#[cfg(feature = "hashes_backend")]
+fn digest_hmac_hasher(
+    algorithm: SupportedAlgorithm,
+    key: &[u8],
+) -> Result<Box<dyn IncrementalHasher>, Error> {
+    match algorithm {
+        SupportedAlgorithm::MD2 => hmac_with_digest::<md2::Md2>(key),
+        SupportedAlgorithm::MD4 => hmac_with_digest::<md4::Md4>(key),
+        SupportedAlgorithm::MD5 => hmac_with_digest::<md5::Md5>(key),
+        SupportedAlgorithm::SHA1 => hmac_with_digest::<sha1::Sha1>(key),
+        SupportedAlgorithm::SHA224 => hmac_with_digest::<sha2::Sha224>(key),
+        SupportedAlgorithm::SHA256 => hmac_with_digest::<sha2::Sha256>(key),
+        SupportedAlgorithm::SHA384 => hmac_with_digest::<sha2::Sha384>(key),
+        SupportedAlgorithm::SHA512 => hmac_with_digest::<sha2::Sha512>(key),
+        SupportedAlgorithm::SHA512_256 => hmac_with_digest::<sha2::Sha512_256>(key),
+        SupportedAlgorithm::SHA3_224 => hmac_with_digest::<sha3::Sha3_224>(key),
+        SupportedAlgorithm::SHA3_256 => hmac_with_digest::<sha3::Sha3_256>(key),
+        SupportedAlgorithm::SHA3_384 => hmac_with_digest::<sha3::Sha3_384>(key),
+        SupportedAlgorithm::SHA3_512 => hmac_with_digest::<sha3::Sha3_512>(key),
+        SupportedAlgorithm::BLAKE2B(_) => hmac_with_digest::<blake2::Blake2b512>(key),
+        SupportedAlgorithm::BLAKE2S(_) => hmac_with_digest::<blake2::Blake2s256>(key),
+        SupportedAlgorithm::SM3 => hmac_with_digest::<sm3::Sm3>(key),
+        SupportedAlgorithm::STREEBOG256 => hmac_with_digest::<streebog::Streebog256>(key),
+        SupportedAlgorithm::STREEBOG512 => hmac_with_digest::<streebog::Streebog512>(key),
+        SupportedAlgorithm::RIPEMD160 => ripemd160_hmac_hasher(key),
+        _ => unreachable!("algorithms without HMAC support are rejected by validate_options"),
+    }
+}
+
+#[cfg(feature = "ring_backend")]
+fn ring_hmac_hasher(algorithm: ring::hmac::Algorithm, key: &[u8]) -> Box<dyn IncrementalHasher> {
+    // ring signs by value, the copy it is given is the only one not wiped.
+    let key = Wiped::new(ring::hmac::Key::new(algorithm, key));
+    streaming(
+        Wiped::new(ring::hmac::Context::with_key(&key)),
+        |context, chunk| context.update(chunk),
+        |context| bytes_to_hex(context.clone().sign().as_ref()),
+    )
+}
+
+fn hmac_hasher(
+    algorithm: SupportedAlgorithm,
+    backend: AlgorithmBackend,
+    key: &[u8],
+) -> Result<Box<dyn IncrementalHasher>, Error> {
+    match (backend, algorithm) {
+        #[cfg(feature = "ring_backend")]
+        (AlgorithmBackend::Ring, SupportedAlgorithm::SHA256) => {
+            Ok(ring_hmac_hasher(ring::hmac::HMAC_SHA256, key))
+        }
+        #[cfg(feature = "ring_backend")]
+        (AlgorithmBackend::Ring, SupportedAlgorithm::SHA384) => {
+            Ok(ring_hmac_hasher(ring::hmac::HMAC_SHA384, key))
+        }
+        #[cfg(feature = "ring_backend")]
+        (AlgorithmBackend::Ring, SupportedAlgorithm::SHA512) => {
+            Ok(ring_hmac_hasher(ring::hmac::HMAC_SHA512, key))
+        }
+        #[cfg(feature = "hashes_backend")]
+        (AlgorithmBackend::Digest, _) => digest_hmac_hasher(algorithm, key),
+        _ => unreachable!("algorithms without HMAC support are rejected by select_backend"),
+    }
+}
+
+/*
+* HMAC needs a fixed-size hash with a block size: SHAKE is an XOF, and BLAKE2 is only
+  supported at its full digest size (keyed BLAKE2 is available through --key instead).
+  ring has no HMAC-SHA512/256, so auto computes it with the hashes backend.
+*/
+const fn supports_hmac(algorithm: SupportedAlgorithm, backend: AlgorithmBackend) -> bool {
+    match algorithm {
+        #[cfg(feature = "hashes_backend")]
+        SupportedAlgorithm::SHAKE128(_) | SupportedAlgorithm::SHAKE256(_) => false,
+        #[cfg(feature = "hashes_backend")]
+        SupportedAlgorithm::BLAKE2B(bits) => bits == BLAKE2B_MAX_BITS,
+        #[cfg(feature = "hashes_backend")]
+        SupportedAlgorithm::BLAKE2S(bits) => bits == BLAKE2S_MAX_BITS,
+        _ => match backend {
+            #[cfg(feature = "ring_backend")]
+            AlgorithmBackend::Ring => !matches!(algorithm, SupportedAlgorithm::SHA512_256),
+            #[cfg(feature = "hashes_backend")]
+            AlgorithmBackend::Digest => true,
+            _ => false,
+        },
+    }
+}
+
+// Picks the implementation of `algorithm` to run, honouring the requested backend and HMAC mode.
+fn select_backend(
+    algorithm: SupportedAlgorithm,
+    options: &HashOptions,
+) -> Result<AlgorithmBackend, Error> {
+    let mut backends = algorithm
+        .backends()
+        .iter()
+        .copied()
+        .filter(|backend| match (options.backend, backend.family()) {
+            (Backend::Auto, _) | (_, None) => true,
+            (requested, Some(family)) => requested == family,
+        })
+        .peekable();
+
+    if backends.peek().is_none() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} is not provided by the {} backend",
+                algorithm, options.backend
+            ),
+        ));
+    }
+
+    if !options.hmac {
+        return Ok(*backends.peek().unwrap());
+    }
+
+    backends
+        .find(|backend| supports_hmac(algorithm, *backend))
+        .ok_or_else(|| {
+            let message = match options.backend {
+                Backend::Auto => format!("{} does not support HMAC", algorithm),
+                backend => format!(
+                    "{} does not support HMAC with the {} backend",
+                    algorithm, backend
+                ),
+            };
+            Error::new(ErrorKind::InvalidInput, message)
+        })
+}
+
+const fn supports_key(algorithm: SupportedAlgorithm) -> bool {
+    match algorithm {
+        SupportedAlgorithm::BLAKE3 => true,
+        #[cfg(feature = "hashes_backend")]
+        SupportedAlgorithm::BLAKE2B(_) | SupportedAlgorithm::BLAKE2S(_) => true,
+        _ => false,
+    }
+}
+
+fn validate_options(
+    algorithm: SupportedAlgorithm,
+    options: &HashOptions,
+) -> Result<AlgorithmBackend, Error> {
+    if options.hmac && options.key.is_none() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "HMAC mode requires a key",
+        ));
+    }
+
+    let backend = select_backend(algorithm, options)?;
+
+    if !options.hmac && options.key.is_some() && !supports_key(algorithm) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} does not support keyed hashing", algorithm),
+        ));
+    }
+
+    if options.derive_key_context.is_some() && algorithm != SupportedAlgorithm::BLAKE3 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} does not support derive-key mode", algorithm),
+        ));
+    }
+
+    if options.seed.is_some() && backend != AlgorithmBackend::Xxhash {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} does not support a seed", algorithm),
+        ));
+    }
+
+    if options.secret.is_some()
+        && !matches!(
+            algorithm,
+            SupportedAlgorithm::XXHASH3_64 | SupportedAlgorithm::XXHASH3_128
+        )
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} does not support a secret", algorithm),
+        ));
+    }
+
+    Ok(backend)
+}
+
+pub fn hash_calculator<R: BufRead>(
+    reader: R,
+    algorithm: SupportedAlgorithm,
+) -> Result<String, Error> {
+    hash_calculator_with_options(reader, algorithm, &HashOptions::default())
+}
+
+// Whether `algorithm` can be computed with `options`, without hashing anything.
+pub fn check_hash_options(
+    algorithm: SupportedAlgorithm,
+    options: &HashOptions,
+) -> Result<(), Error> {
+    validate_options(algorithm, options).map(|_| ())
+}
+
+// Validates `options` for `algorithm` and returns a hasher ready to be fed the input.
+pub fn incremental_hasher(
+    algorithm: SupportedAlgorithm,
+    options: &HashOptions,
+) -> Result<Box<dyn IncrementalHasher>, Error> {
+    let backend = validate_options(algorithm, options)?;
+
+    if let (true, Some(key)) = (options.hmac, &options.key) {
+        return hmac_hasher(algorithm, backend, key);
+    }
+
+    match backend {
+        AlgorithmBackend::Xxhash => xxhash_hasher(algorithm, options),
+        AlgorithmBackend::Blake3 => blake3_hasher(options),
+        AlgorithmBackend::Crc => Ok(crc_hasher(algorithm)),
+        AlgorithmBackend::Sum => Ok(sum_hasher(algorithm)),
+        #[cfg(feature = "ring_backend")]
+        AlgorithmBackend::Ring => Ok(ring_hasher(algorithm)),
+        #[cfg(feature = "hashes_backend")]
+        AlgorithmBackend::Digest => digest_hasher(algorithm, options),
+    }
+}
+
+pub fn hash_calculator_with_options<R: BufRead>(
+    mut reader: R,
+    algorithm: SupportedAlgorithm,
+    options: &HashOptions,
+) -> Result<String, Error> {
+    let mut hasher = incremental_hasher(algorithm, options)?;
+    consume_reader(&mut reader, |chunk| hasher.update(chunk))?;
+
+    Ok(hasher.finalize())
+}
+
+pub fn hash_calculator_multi<R: BufRead>(
+    reader: R,
+    algorithms: &[SupportedAlgorithm],
+) -> Result<Vec<(SupportedAlgorithm, String)>, Error> {
+    hash_calculator_multi_with_options(reader, algorithms, &HashOptions::default())
+}
+
+// Reads the input once and feeds every chunk to a hasher per algorithm.
+pub fn hash_calculator_multi_with_options<R: BufRead>(
+    mut reader: R,
+    algorithms: &[SupportedAlgorithm],
+    options: &HashOptions,
+) -> Result<Vec<(SupportedAlgorithm, String)>, Error> {
+    let mut hashers = algorithms
+        .iter()
+        .map(|&algorithm| incremental_hasher(algorithm, options))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    consume_reader(&mut reader, |chunk| {
+        for hasher in hashers.iter_mut() {
+            hasher.update(chunk);
+        }
+    })?;
+
+    Ok(algorithms
+        .iter()
+        .copied()
+        .zip(hashers.into_iter().map(|hasher| hasher.finalize()))
+        .collect())
+}
+
+#[cfg(test)]
+mod test_calculator {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    const TEST_WORD: &[u8; 16] = b"Veni, vidi, vici";
+
+    #[test]
+    fn test_xxhash32() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::XXHASH32).unwrap(),
+            "0163d3a2"
+        );
+    }
+
+    #[test]
+    fn test_xxhash64() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::XXHASH64).unwrap(),
+            "4a34911ba20e6c30"
+        );
+    }
+
+    #[test]
+    fn test_xxhash3_64() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::XXHASH3_64).unwrap(),
+            "XXH3_802c0db623389036"
+        );
+    }
+
+    #[test]
+    fn test_xxhash3_128() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::XXHASH3_128).unwrap(),
+            "XXH3_39b4146c8a787726798df846da630b0d"
+        );
+    }
+
+    // Sanity vectors from xxHash's cli/xsum_sanity_check.c, empty input.
+    #[test]
+    fn test_xxhash_seeded_empty_input() {
+        let options = HashOptions {
+            seed: Some(2654435761),
+            ..HashOptions::default()
+        };
+        assert_eq!(
+            hash_calculator_with_options(&b""[..], SupportedAlgorithm::XXHASH32, &options).unwrap(),
+            "36b78ae7"
+        );
+        assert_eq!(
+            hash_calculator_with_options(&b""[..], SupportedAlgorithm::XXHASH64, &options).unwrap(),
+            "ac75fda2929b17ef"
+        );
+        assert_eq!(
+            hash_calculator(&b""[..], SupportedAlgorithm::XXHASH3_128).unwrap(),
+            "XXH3_99aa06d3014798d86001c324468d497f"
+        );
+    }
+
+    // Like XXH3_*_withSecretandSeed, inputs up to 240 bytes ignore the secret, so hash a longer file.
+    #[test]
+    fn test_xxhash3_with_secret() {
+        let reader = BufReader::new(File::open("tests/滕王阁序.txt").unwrap());
+        let options = HashOptions {
+            secret: Some(Zeroizing::new((0..=255).collect())),
+            ..HashOptions::default()
+        };
+        assert_eq!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::XXHASH3_128, &options)
+                .unwrap(),
+            "XXH3_58bede4556fb9b7948437fcc8fcbb850"
+        );
+    }
+
+    #[test]
+    fn test_xxhash3_rejects_short_secret() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        let options = HashOptions {
+            secret: Some(Zeroizing::new(vec![0x42; XXH3_SECRET_MIN_LEN - 1])),
+            ..HashOptions::default()
+        };
+        assert!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::XXHASH3_64, &options).is_err()
+        );
+    }
+
+    #[test]
+    fn test_seed_is_rejected_for_non_xxhash_algorithms() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        let options = HashOptions {
+            seed: Some(42),
+            ..HashOptions::default()
+        };
+        assert!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::SHA256, &options).is_err()
+        );
+    }
+
+    #[test]
+    fn test_xxhash32_rejects_seed_wider_than_32_bits() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        let options = HashOptions {
+            seed: Some(u64::from(u32::MAX) + 1),
+            ..HashOptions::default()
+        };
+        assert!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::XXHASH32, &options).is_err()
+        );
+    }
+
+    #[test]
+    fn test_blake3() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::BLAKE3).unwrap(),
+            "d2e22594a4ca65591cb6dfa03003c235dc66856ad8d5ce9afa2757b975a28160"
+        );
+    }
+
+    #[test]
+    fn test_blake3_large_input_matches_single_update() {
+        let data: Vec<u8> = (0..BLAKE3_BUFFER_SIZE * 3 + 1234)
+            .map(|index| (index % 251) as u8)
+            .collect();
+        assert_eq!(
+            hash_calculator(BufReader::new(data.as_slice()), SupportedAlgorithm::BLAKE3).unwrap(),
+            blake3::hash(&data).to_hex().as_str()
+        );
+    }
+
+    #[test]
+    fn test_blake3_keyed() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        let options = HashOptions {
+            key: Some(Zeroizing::new(vec![0x42; BLAKE3_KEY_LEN])),
+            ..HashOptions::default()
+        };
+        assert_eq!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::BLAKE3, &options).unwrap(),
+            "88615b49079cb938f92c7f4670e6d92cb4e47704b8e533c1f42f9e94a353565a"
+        );
+    }
+
+    #[test]
+    fn test_blake3_derive_key() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        let options = HashOptions {
+            derive_key_context: Some(String::from("ezcheck 2024-01-01 test context")),
+            ..HashOptions::default()
+        };
+        assert_eq!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::BLAKE3, &options).unwrap(),
+            "a1a0eab14d5fbce8bdb93d5f5756cde31ce20e93a31d5e1e11c9b96628037684"
+        );
+    }
+
+    #[test]
+    fn test_blake3_rejects_short_key() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        let options = HashOptions {
+            key: Some(Zeroizing::new(vec![0x42; 16])),
+            ..HashOptions::default()
+        };
+        assert!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::BLAKE3, &options).is_err()
+        );
+    }
+
+    #[test]
+    fn test_key_is_rejected_for_unkeyed_algorithms() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        let options = HashOptions {
+            key: Some(Zeroizing::new(vec![0x42; BLAKE3_KEY_LEN])),
+            ..HashOptions::default()
+        };
+        assert!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::SHA256, &options).is_err()
+        );
+    }
+
+    // RFC 4231 test case 2 and RFC 2202 test case 2.
+    const HMAC_KEY: &[u8] = b"Jefe";
+    const HMAC_DATA: &[u8] = b"what do ya want for nothing?";
+
+    fn hmac_options() -> HashOptions {
+        HashOptions {
+            key: Some(Zeroizing::new(HMAC_KEY.to_vec())),
+            hmac: true,
+            ..HashOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        assert_eq!(
+            hash_calculator_with_options(HMAC_DATA, SupportedAlgorithm::SHA256, &hmac_options())
+                .unwrap(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha512() {
+        assert_eq!(
+            hash_calculator_with_options(HMAC_DATA, SupportedAlgorithm::SHA512, &hmac_options())
+                .unwrap(),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_hmac_sha1() {
+        assert_eq!(
+            hash_calculator_with_options(HMAC_DATA, SupportedAlgorithm::SHA1, &hmac_options())
+                .unwrap(),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_hmac_sha512_256() {
+        assert_eq!(
+            hash_calculator_with_options(
+                HMAC_DATA,
+                SupportedAlgorithm::SHA512_256,
+                &hmac_options()
+            )
+            .unwrap(),
+            "6df7b24630d5ccb2ee335407081a87188c221489768fa2020513b2d593359456"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_hmac_sha3_256() {
+        assert_eq!(
+            hash_calculator_with_options(HMAC_DATA, SupportedAlgorithm::SHA3_256, &hmac_options())
+                .unwrap(),
+            "c7d4072e788877ae3596bbb0da73b887c9171f93095b294ae857fbe2645e1ba5"
+        );
+    }
+
+    // RFC 2286 test case 2.
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_hmac_ripemd160() {
+        assert_eq!(
+            hash_calculator_with_options(HMAC_DATA, SupportedAlgorithm::RIPEMD160, &hmac_options())
+                .unwrap(),
+            "dda6c0213a485a9e24f4742064a7f033b43c4069"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_hmac_rejects_xof_and_truncated_blake2() {
+        assert!(hash_calculator_with_options(
+            HMAC_DATA,
+            SupportedAlgorithm::SHAKE128(256),
+            &hmac_options()
+        )
+        .is_err());
+        assert!(hash_calculator_with_options(
+            HMAC_DATA,
+            SupportedAlgorithm::BLAKE2B(256),
+            &hmac_options()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_hmac_requires_key_and_digest_algorithm() {
+        let options = HashOptions {
+            hmac: true,
+            ..HashOptions::default()
+        };
+        assert!(
+            hash_calculator_with_options(HMAC_DATA, SupportedAlgorithm::SHA256, &options).is_err()
+        );
+        assert!(hash_calculator_with_options(
+            HMAC_DATA,
+            SupportedAlgorithm::XXHASH64,
+            &hmac_options()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_crc32() {
+        let reader = BufReader::new(&b"123456789"[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::CRC32).unwrap(),
+            "cbf43926"
+        );
+    }
+
+    #[test]
+    fn test_crc32c() {
+        let reader = BufReader::new(&b"123456789"[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::CRC32C).unwrap(),
+            "e3069283"
+        );
+    }
+
+    #[test]
+    fn test_crc64() {
+        let reader = BufReader::new(&b"123456789"[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::CRC64).unwrap(),
+            "995dc9bbdf1939fa"
+        );
+    }
+
+    #[test]
+    fn test_crc_across_buffer_boundaries() {
+        let data = vec![0xa5u8; BUFFER_SIZE * 2 + 17];
+        assert_eq!(
+            hash_calculator(BufReader::new(data.as_slice()), SupportedAlgorithm::CRC32C).unwrap(),
+            format!("{:08x}", crc32c::crc32c(&data))
+        );
+    }
+
+    #[test]
+    fn test_cksum() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::CKSUM).unwrap(),
+            "2941961746 16"
+        );
+        assert_eq!(
+            hash_calculator(BufReader::new(&b""[..]), SupportedAlgorithm::CKSUM).unwrap(),
+            "4294967295 0"
+        );
+    }
+
+    #[test]
+    fn test_bsd_sum() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::BSDSUM).unwrap(),
+            "02698     1"
+        );
+    }
+
+    #[test]
+    fn test_sysv_sum() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::SYSVSUM).unwrap(),
+            "1409 1"
+        );
+    }
+
+    #[test]
+    fn test_sums_read_file() {
+        let test_file = "tests/滕王阁序.txt";
+        for (algorithm, expected) in [
+            (SupportedAlgorithm::CKSUM, "2093067988 2794"),
+            (SupportedAlgorithm::BSDSUM, "47942     3"),
+            (SupportedAlgorithm::SYSVSUM, "52466 6"),
+        ] {
+            let reader = BufReader::new(File::open(test_file).unwrap());
+            assert_eq!(hash_calculator(reader, algorithm).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_supported_algorithm_from_input_accepts_case_insensitive_aliases() {
+        assert_eq!(
             SupportedAlgorithm::from_input("sHa512/256").unwrap(),
             SupportedAlgorithm::SHA512_256
         );
@@ -327,9 +1749,25 @@ mod test_calculator {
             SupportedAlgorithm::XXHASH3_64.prefixed_hash_name(),
             "xxh3_64"
         );
+        assert_eq!(
+            SupportedAlgorithm::XXHASH3_128.prefixed_hash_name(),
+            "xxh3_128"
+        );
+    }
+
+    #[test]
+    fn test_supported_algorithm_tag_name_round_trips() {
+        for &algorithm in ALL_ALGORITHMS {
+            assert_eq!(
+                SupportedAlgorithm::from_input(algorithm.tag_name()).unwrap(),
+                algorithm
+            );
+        }
+        assert_eq!(SupportedAlgorithm::SHA256.tag_name(), "SHA256");
+        assert_eq!(SupportedAlgorithm::XXHASH3_64.tag_name(), "XXH3");
     }
 
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     #[test]
     fn test_md2() {
         let reader = BufReader::new(&TEST_WORD[..]);
@@ -339,7 +1777,7 @@ mod test_calculator {
         );
     }
 
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     #[test]
     fn test_md4() {
         let reader = BufReader::new(&TEST_WORD[..]);
@@ -349,7 +1787,7 @@ mod test_calculator {
         );
     }
 
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     #[test]
     fn test_md5() {
         let reader = BufReader::new(&TEST_WORD[..]);
@@ -359,7 +1797,7 @@ mod test_calculator {
         );
     }
 
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     #[test]
     fn test_sha1() {
         let reader = BufReader::new(&TEST_WORD[..]);
@@ -369,7 +1807,7 @@ mod test_calculator {
         );
     }
 
-    #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
+    #[cfg(feature = "hashes_backend")]
     #[test]
     fn test_sha224() {
         let reader = BufReader::new(&TEST_WORD[..]);
@@ -379,6 +1817,248 @@ mod test_calculator {
         );
     }
 
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_sha3_224() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::SHA3_224).unwrap(),
+            "d4c788e506532289ee2ac497129232aeae4b9fc844937563a82b975b"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_sha3_256() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::SHA3_256).unwrap(),
+            "068009216f09d4789424adfb4c1f9d08e0f1687abf6e0197bc978b297863733f"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_sha3_384() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::SHA3_384).unwrap(),
+            "79a8a4b5452637776f4cfdb72598272abcd0b697b3a62fb7380021e1a3dc0e2139e3035ac9d938a17bf50be333ebd045"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_sha3_512() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::SHA3_512).unwrap(),
+            "de2a43b01c7d1699a2134f7834e6e956372b5195cf9359e7896a5c7d99ce939edbfc8bea9f603131b552e4f7b101ea21085fd5108ca1fd6a2bed6bb7f0991c4b"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_shake128() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::SHAKE128(256)).unwrap(),
+            "2b48a1cecb0ad4639b5d7c73d63048f89d8c9218715badba266c2e65eb36acf6"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_shake256_with_custom_length() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::SHAKE256(128)).unwrap(),
+            "b14ae8a10ed513c3c43583011c483ff0"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_supported_algorithm_from_input_parses_shake_output_length() {
+        assert_eq!(
+            SupportedAlgorithm::from_input("SHAKE256/512").unwrap(),
+            SupportedAlgorithm::SHAKE256(512)
+        );
+        assert_eq!(
+            SupportedAlgorithm::from_input("shake128").unwrap(),
+            SupportedAlgorithm::SHAKE128(256)
+        );
+        assert_eq!(
+            SupportedAlgorithm::SHAKE256(1024).prefixed_hash_name(),
+            "shake256/1024"
+        );
+        assert!(SupportedAlgorithm::from_input("shake256/12").is_err());
+        assert!(SupportedAlgorithm::from_input("shake256/0").is_err());
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_blake2b() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::BLAKE2B(512)).unwrap(),
+            "ea4d0c30dfd140932590553f58077658321066699969db2604398887ab489018e156d0918335e41d25f2f87626e2b8dd21accfa70b89b415e8c62e246d1e24c4"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_blake2b_with_custom_length() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::BLAKE2B(256)).unwrap(),
+            "fa4d44ff6688ebb61fcc53c93f99c49f26d380ac2a9d2f9a3e7c221d9da2d411"
+        );
+
+        let data = [b'x'; 300];
+        assert_eq!(
+            hash_calculator(BufReader::new(&data[..]), SupportedAlgorithm::BLAKE2B(160)).unwrap(),
+            "5cdff8b6d7145ae42f4dabf69e299160b8ce7a30"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_blake2b_keyed() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        let options = HashOptions {
+            key: Some(Zeroizing::new(vec![0x42; 64])),
+            ..HashOptions::default()
+        };
+        assert_eq!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::BLAKE2B(512), &options)
+                .unwrap(),
+            "63806ad3ee74d6e6961c5a821861dd85164830ad6202a909635dae850fc6aed8dcf8ae8c8cc6ac9c4193f8ac3fe0a4eb373646c7f7b597865eeb5291aa993f26"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_blake2s() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::BLAKE2S(256)).unwrap(),
+            "1f3524b543407395f1ae24fb85f557811a3c24ef58a128831a9a791367d56b2e"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_blake2s_keyed_with_custom_length() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        let options = HashOptions {
+            key: Some(Zeroizing::new(vec![0x42; 16])),
+            ..HashOptions::default()
+        };
+        assert_eq!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::BLAKE2S(128), &options)
+                .unwrap(),
+            "88f9ad321304cac9d73b3c819db23e42"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_blake2s_rejects_oversized_key() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        let options = HashOptions {
+            key: Some(Zeroizing::new(vec![0x42; 33])),
+            ..HashOptions::default()
+        };
+        assert!(
+            hash_calculator_with_options(reader, SupportedAlgorithm::BLAKE2S(256), &options)
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_supported_algorithm_from_input_parses_blake2_output_length() {
+        assert_eq!(
+            SupportedAlgorithm::from_input("BLAKE2b/256").unwrap(),
+            SupportedAlgorithm::BLAKE2B(256)
+        );
+        assert_eq!(
+            SupportedAlgorithm::from_input("blake2s256").unwrap(),
+            SupportedAlgorithm::BLAKE2S(256)
+        );
+        assert!(SupportedAlgorithm::from_input("blake2s/512").is_err());
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_sm3() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::SM3).unwrap(),
+            "69cd05fdd92d070e09d93a1a2607223e38b1cba945a65d57d78ba7c88fbe701d"
+        );
+    }
+
+    // GB/T 32905-2016 appendix A, example 1.
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_sm3_standard_example() {
+        assert_eq!(
+            hash_calculator(&b"abc"[..], SupportedAlgorithm::SM3).unwrap(),
+            "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0"
+        );
+    }
+
+    // GOST R 34.11-2012 appendix A, example 1.
+    #[cfg(feature = "hashes_backend")]
+    const STREEBOG_M1: &[u8] = b"012345678901234567890123456789012345678901234567890123456789012";
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_streebog256() {
+        assert_eq!(
+            hash_calculator(STREEBOG_M1, SupportedAlgorithm::STREEBOG256).unwrap(),
+            "9d151eefd8590b89daa6ba6cb74af9275dd051026bb149a452fd84e5e57b5500"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_streebog512() {
+        assert_eq!(
+            hash_calculator(STREEBOG_M1, SupportedAlgorithm::STREEBOG512).unwrap(),
+            "1b54d01a4af5b9d5cc3d86d68d285462b19abc2475222f35c085122be4ba1ffa00ad30f8767b3a82384c6574f024c311e2a481332b08ef7f41797891c1646f48"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_ripemd160() {
+        let reader = BufReader::new(&TEST_WORD[..]);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::RIPEMD160).unwrap(),
+            "a3347df03c540d5b86bef2bb6d103a786aca8119"
+        );
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_supported_algorithm_from_input_accepts_regional_hash_aliases() {
+        assert_eq!(
+            SupportedAlgorithm::from_input("GOST2012-256").unwrap(),
+            SupportedAlgorithm::STREEBOG256
+        );
+        assert_eq!(
+            SupportedAlgorithm::from_input("rmd160").unwrap(),
+            SupportedAlgorithm::RIPEMD160
+        );
+        assert_eq!(
+            SupportedAlgorithm::STREEBOG512.prefixed_hash_name(),
+            "streebog512"
+        );
+    }
+
     #[test]
     fn test_sha256() {
         let reader = BufReader::new(&TEST_WORD[..]);
@@ -426,4 +2106,94 @@ mod test_calculator {
             "00691413c731ee37f551bfaca6a34b8443b3e85d7c0816a6fe90aa8fc8eaec95"
         );
     }
+
+    #[cfg(all(feature = "ring_backend", feature = "hashes_backend"))]
+    #[test]
+    fn test_backends_agree() {
+        for backend in [Backend::Auto, Backend::Ring, Backend::Hashes] {
+            let options = HashOptions {
+                backend,
+                ..HashOptions::default()
+            };
+            assert_eq!(
+                hash_calculator_with_options(
+                    &TEST_WORD[..],
+                    SupportedAlgorithm::SHA512_256,
+                    &options
+                )
+                .unwrap(),
+                hash_calculator(&TEST_WORD[..], SupportedAlgorithm::SHA512_256).unwrap()
+            );
+        }
+    }
+
+    #[cfg(feature = "ring_backend")]
+    #[test]
+    fn test_ring_backend_rejects_other_algorithms() {
+        let options = HashOptions {
+            backend: Backend::Ring,
+            ..HashOptions::default()
+        };
+        // Algorithms with a single implementation ignore the requested backend.
+        assert!(
+            hash_calculator_with_options(&TEST_WORD[..], SupportedAlgorithm::CRC32, &options)
+                .is_ok()
+        );
+
+        let options = HashOptions {
+            key: Some(Zeroizing::new(HMAC_KEY.to_vec())),
+            hmac: true,
+            backend: Backend::Ring,
+            ..HashOptions::default()
+        };
+        assert!(
+            hash_calculator_with_options(HMAC_DATA, SupportedAlgorithm::SHA512_256, &options)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_backend_from_input() {
+        assert_eq!(Backend::from_input("Auto").unwrap(), Backend::Auto);
+        assert!(Backend::from_input("openssl").is_err());
+        assert_eq!(
+            Backend::from_input("ring").is_ok(),
+            cfg!(feature = "ring_backend")
+        );
+        assert_eq!(
+            Backend::from_input("hashes").is_ok(),
+            cfg!(feature = "hashes_backend")
+        );
+    }
+
+    #[test]
+    fn test_hash_calculator_multi_matches_single_pass() {
+        let reader = BufReader::with_capacity(64, File::open("tests/滕王阁序.txt").unwrap());
+        let hashes = hash_calculator_multi(reader, ALL_ALGORITHMS).unwrap();
+
+        assert_eq!(hashes.len(), ALL_ALGORITHMS.len());
+        for (algorithm, hash) in hashes {
+            let reader = BufReader::new(File::open("tests/滕王阁序.txt").unwrap());
+            assert_eq!(
+                hash,
+                hash_calculator(reader, algorithm).unwrap(),
+                "{}",
+                algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_calculator_multi_rejects_unsupported_options() {
+        let options = HashOptions {
+            seed: Some(1),
+            ..HashOptions::default()
+        };
+        assert!(hash_calculator_multi_with_options(
+            &TEST_WORD[..],
+            &[SupportedAlgorithm::XXHASH64, SupportedAlgorithm::SHA256],
+            &options
+        )
+        .is_err());
+    }
 }
@@ -4,23 +4,179 @@
     feature = "mix_backend"
 )))]
 compile_error!("You must enable at least one of the features: 'hashes_backend', 'ring_backend' or 'mix_backend'.");
-#[cfg(any(
-    all(feature = "hashes_backend", feature = "ring_backend"),
-    all(feature = "hashes_backend", feature = "mix_backend"),
-    all(feature = "ring_backend", feature = "mix_backend"),
-    all(
-        feature = "hashes_backend",
-        feature = "ring_backend",
-        feature = "mix_backend"
-    )
-))]
-compile_error!(
-    "Only one of the features `hashes_backend`, `ring_backend`, or `mix_backend` can be enabled at a time."
-);
 
 use crate::calculator::SupportedAlgorithm;
+use data_encoding::{
+    Encoding, BASE32, BASE32_NOPAD, BASE64, BASE64URL, BASE64URL_NOPAD, BASE64_NOPAD,
+};
 use std::fmt::Write;
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputEncoding {
+    #[default]
+    Hex,
+    UpperHex,
+    Base64,
+    Base64Url,
+    Base32,
+    Nix32,
+}
+
+impl OutputEncoding {
+    pub fn from_input<S: AsRef<str>>(encoding: S) -> Result<Self, String> {
+        let encoding = encoding.as_ref().trim();
+
+        // Upper case "HEX" asks for upper case digits, every other spelling of hex is lower case.
+        if encoding == "HEX" {
+            return Ok(Self::UpperHex);
+        }
+
+        match encoding.to_ascii_lowercase().as_str() {
+            "hex" => Ok(Self::Hex),
+            "upper-hex" | "hex-upper" => Ok(Self::UpperHex),
+            "base64" | "b64" => Ok(Self::Base64),
+            "base64url" | "base64-url" | "b64url" => Ok(Self::Base64Url),
+            "base32" | "b32" => Ok(Self::Base32),
+            "nix32" | "nix-base32" | "nixbase32" => Ok(Self::Nix32),
+            _ => Err(format!("Error: Unsupported encoding: {}", encoding)),
+        }
+    }
+
+    /*
+     * base64 and base32 are padded as in RFC 4648, base64url is not, as in JWT and most URLs.
+     */
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            OutputEncoding::Hex => bytes_to_hex(bytes),
+            OutputEncoding::UpperHex => bytes_to_hex(bytes).to_ascii_uppercase(),
+            OutputEncoding::Base64 => BASE64.encode(bytes),
+            OutputEncoding::Base64Url => BASE64URL_NOPAD.encode(bytes),
+            OutputEncoding::Base32 => BASE32.encode(bytes),
+            OutputEncoding::Nix32 => nix32_encode(bytes),
+        }
+    }
+}
+
+// Nix's base32 alphabet omits e, o, u and t, and emits the most significant digit first.
+const NIX32_ALPHABET: &[u8; 32] = b"0123456789abcdfghijklmnpqrsvwxyz";
+
+fn nix32_encode(bytes: &[u8]) -> String {
+    let length = (bytes.len() * 8).div_ceil(5);
+
+    (0..length)
+        .rev()
+        .map(|digit| {
+            let bit = digit * 5;
+            let index = bit / 8;
+            let window = u16::from(bytes[index])
+                | bytes.get(index + 1).map_or(0, |&byte| u16::from(byte) << 8);
+            NIX32_ALPHABET[usize::from((window >> (bit % 8)) & 0x1f)] as char
+        })
+        .collect()
+}
+
+fn nix32_decode(input: &str) -> Option<Vec<u8>> {
+    let size = input.len() * 5 / 8;
+    if size == 0 {
+        return None;
+    }
+
+    let mut bytes = vec![0u8; size];
+    for (digit, character) in input.bytes().rev().enumerate() {
+        let value = NIX32_ALPHABET
+            .iter()
+            .position(|&symbol| symbol == character)? as u16;
+        let bit = digit * 5;
+        let index = bit / 8;
+        let shifted = value << (bit % 8);
+
+        for (offset, part) in [shifted as u8, (shifted >> 8) as u8]
+            .into_iter()
+            .enumerate()
+        {
+            match bytes.get_mut(index + offset) {
+                Some(byte) => *byte |= part,
+                None if part != 0 => return None,
+                None => {}
+            }
+        }
+    }
+
+    Some(bytes)
+}
+
+const BASE64_ENCODINGS: [&Encoding; 4] = [&BASE64, &BASE64_NOPAD, &BASE64URL, &BASE64URL_NOPAD];
+
+// Every byte string `hash` decodes to, one for each encoding it is valid in.
+pub fn decode_hash(hash: &str) -> Vec<Vec<u8>> {
+    let hash = hash.trim();
+    let mut candidates = Vec::new();
+    let mut push = |bytes: Vec<u8>| {
+        if !bytes.is_empty() && !candidates.contains(&bytes) {
+            candidates.push(bytes);
+        }
+    };
+
+    if let Ok(bytes) = hex_to_bytes(hash) {
+        push(bytes);
+    }
+
+    // A base64 digest without a single upper case letter or symbol is vanishingly
+    // unlikely, such strings are far more likely to be mistyped hex.
+    if hash
+        .chars()
+        .any(|c| c.is_ascii_uppercase() || "+/-_=".contains(c))
+    {
+        for encoding in BASE64_ENCODINGS {
+            if let Ok(bytes) = encoding.decode(hash.as_bytes()) {
+                push(bytes);
+            }
+        }
+    }
+
+    // base32 is case-insensitive, data-encoding only accepts upper case.
+    for encoding in [&BASE32, &BASE32_NOPAD] {
+        if let Ok(bytes) = encoding.decode(hash.to_ascii_uppercase().as_bytes()) {
+            push(bytes);
+        }
+    }
+
+    if let Some(bytes) = nix32_decode(hash) {
+        push(bytes);
+    }
+
+    candidates
+}
+
+pub fn encode_hash(
+    algorithm: SupportedAlgorithm,
+    hash: &str,
+    encoding: OutputEncoding,
+) -> Result<String, String> {
+    if encoding == OutputEncoding::Hex {
+        return Ok(hash.to_string());
+    }
+
+    if algorithm.has_size_column() {
+        return Err(format!(
+            "Error: {} prints a decimal checksum and cannot be re-encoded.",
+            algorithm
+        ));
+    }
+
+    // XXH3 values keep their prefix in upper case hex, other encodings print just the digest.
+    let (prefix, hash) = match strip_xxh3_prefix(hash) {
+        Some(hash) if encoding == OutputEncoding::UpperHex => ("XXH3_", hash),
+        Some(hash) => ("", hash),
+        None => ("", hash),
+    };
+    Ok(format!(
+        "{}{}",
+        prefix,
+        encoding.encode(&hex_to_bytes(hash)?)
+    ))
+}
+
 pub fn bytes_to_hex(bytes: &[u8]) -> String {
     let mut hex_string = String::with_capacity(bytes.len() * 2);
     for byte in bytes {
@@ -29,41 +185,232 @@ pub fn bytes_to_hex(bytes: &[u8]) -> String {
     hex_string
 }
 
+pub fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.trim();
+    if hex.len() % 2 != 0 || !is_ascii_hex(hex) {
+        return Err(String::from("Error: Invalid hex string."));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| {
+            u8::from_str_radix(&hex[index..index + 2], 16)
+                .map_err(|_| String::from("Error: Invalid hex string."))
+        })
+        .collect()
+}
+
 fn is_ascii_hex(input: &str) -> bool {
     input.bytes().all(|byte| byte.is_ascii_hexdigit())
 }
 
-fn is_xxh3_64(input: &str) -> bool {
+pub fn strip_xxh3_prefix(input: &str) -> Option<&str> {
     input
         .strip_prefix("XXH3_")
         .or_else(|| input.strip_prefix("xxh3_"))
-        .is_some_and(is_ascii_hex)
 }
 
-pub fn detect_hash_algorithm<S: AsRef<str>>(hash: S) -> Result<Vec<SupportedAlgorithm>, String> {
-    let hash = hash.as_ref();
+fn is_xxh3(input: &str) -> bool {
+    strip_xxh3_prefix(input).is_some_and(is_ascii_hex)
+}
 
+// "<checksum> <size>" as printed by cksum, sum -r and sum -s.
+fn is_decimal_checksum(input: &str) -> bool {
+    let mut columns = input.split_whitespace();
+    matches!(
+        (columns.next(), columns.next(), columns.next()),
+        (Some(checksum), Some(size), None)
+            if checksum.bytes().all(|byte| byte.is_ascii_digit())
+                && size.bytes().all(|byte| byte.is_ascii_digit())
+    )
+}
+
+fn parse_decimal_checksum(input: &str) -> Option<(u64, u64)> {
+    let mut columns = input.split_whitespace();
+    let checksum = columns.next()?.parse().ok()?;
+    let size = columns.next()?.parse().ok()?;
+    columns.next().is_none().then_some((checksum, size))
+}
+
+pub fn hash_matches(algorithm: SupportedAlgorithm, computed: &str, expected: &str) -> bool {
+    if algorithm.has_size_column() {
+        return matches!(
+            (parse_decimal_checksum(computed), parse_decimal_checksum(expected)),
+            (Some(computed), Some(expected)) if computed == expected
+        );
+    }
+
+    // xxhsum prints XXH3 values without the "XXH3_" prefix, accept both forms.
+    let (computed, expected) = if matches!(
+        algorithm,
+        SupportedAlgorithm::XXHASH3_64 | SupportedAlgorithm::XXHASH3_128
+    ) {
+        (
+            strip_xxh3_prefix(computed).unwrap_or(computed),
+            strip_xxh3_prefix(expected).unwrap_or(expected),
+        )
+    } else {
+        (computed, expected)
+    };
+
+    if computed.eq_ignore_ascii_case(expected) {
+        return true;
+    }
+
+    // The expected hash may be given in any supported encoding.
+    match hex_to_bytes(computed) {
+        Ok(computed) => decode_hash(expected).contains(&computed),
+        Err(_) => false,
+    }
+}
+
+/*
+* GNU coreutils escaping of file names in checksum lines: a name containing a backslash, newline
+  or carriage return is written with those escaped as \\, \n and \r, and the whole line then
+  starts with a backslash. None when the name needs no escaping.
+*/
+pub fn escape_file_name(name: &str) -> Option<String> {
+    if !name.contains(['\\', '\n', '\r']) {
+        return None;
+    }
+    Some(
+        name.replace('\\', "\\\\")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r"),
+    )
+}
+
+pub fn unescape_file_name(name: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            _ => return Err(format!("Error: Invalid escape in file name: {}", name)),
+        }
+    }
+    Ok(unescaped)
+}
+
+// Seeds are given in decimal or as 0x-prefixed hex.
+pub fn parse_seed(seed: &str) -> Result<u64, String> {
+    let seed = seed.trim();
+    let parsed = match seed.strip_prefix("0x").or_else(|| seed.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => seed.parse(),
+    };
+
+    parsed.map_err(|_| format!("Error: Invalid seed: {}", seed))
+}
+
+pub fn is_hash_of_bits(hash: &str, bits: usize) -> bool {
+    decode_hash(hash)
+        .iter()
+        .any(|bytes| bytes.len() * 8 == bits)
+}
+
+pub(crate) fn detect_hex_hash_algorithm(hash: &str) -> Option<Vec<SupportedAlgorithm>> {
     match hash.len() {
-        8 if is_ascii_hex(hash) => Ok(vec![SupportedAlgorithm::XXHASH32]),
-        16 if is_ascii_hex(hash) => Ok(vec![SupportedAlgorithm::XXHASH64]),
-        21 if is_xxh3_64(hash) => Ok(vec![SupportedAlgorithm::XXHASH3_64]),
-        #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
-        32 if is_ascii_hex(hash) => Ok(vec![
+        8 if is_ascii_hex(hash) => Some(vec![
+            SupportedAlgorithm::XXHASH32,
+            SupportedAlgorithm::CRC32,
+            SupportedAlgorithm::CRC32C,
+        ]),
+        16 if is_ascii_hex(hash) => Some(vec![
+            SupportedAlgorithm::XXHASH64,
+            SupportedAlgorithm::CRC64,
+        ]),
+        21 if is_xxh3(hash) => Some(vec![SupportedAlgorithm::XXHASH3_64]),
+        32 if is_ascii_hex(hash) => Some(vec![
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::MD5,
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::MD4,
+            #[cfg(feature = "hashes_backend")]
             SupportedAlgorithm::MD2,
+            SupportedAlgorithm::XXHASH3_128,
         ]),
-        #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
-        40 if is_ascii_hex(hash) => Ok(vec![SupportedAlgorithm::SHA1]),
-        #[cfg(any(feature = "hashes_backend", feature = "mix_backend"))]
-        56 if is_ascii_hex(hash) => Ok(vec![SupportedAlgorithm::SHA224]),
-        64 if is_ascii_hex(hash) => Ok(vec![
+        37 if is_xxh3(hash) => Some(vec![SupportedAlgorithm::XXHASH3_128]),
+        #[cfg(feature = "hashes_backend")]
+        40 if is_ascii_hex(hash) => Some(vec![
+            SupportedAlgorithm::SHA1,
+            SupportedAlgorithm::RIPEMD160,
+        ]),
+        #[cfg(feature = "hashes_backend")]
+        56 if is_ascii_hex(hash) => Some(vec![
+            SupportedAlgorithm::SHA224,
+            SupportedAlgorithm::SHA3_224,
+        ]),
+        64 if is_ascii_hex(hash) => Some(vec![
             SupportedAlgorithm::SHA256,
             SupportedAlgorithm::SHA512_256,
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_256,
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::BLAKE2S(256),
+            SupportedAlgorithm::BLAKE3,
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHAKE128(256),
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SM3,
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::STREEBOG256,
+        ]),
+        96 if is_ascii_hex(hash) => Some(vec![
+            SupportedAlgorithm::SHA384,
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_384,
         ]),
-        96 if is_ascii_hex(hash) => Ok(vec![SupportedAlgorithm::SHA384]),
-        128 if is_ascii_hex(hash) => Ok(vec![SupportedAlgorithm::SHA512]),
-        _ => Err(String::from("Error: Invalid hash.")),
+        128 if is_ascii_hex(hash) => Some(vec![
+            SupportedAlgorithm::SHA512,
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHA3_512,
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::BLAKE2B(512),
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::SHAKE256(512),
+            #[cfg(feature = "hashes_backend")]
+            SupportedAlgorithm::STREEBOG512,
+        ]),
+        _ => None,
+    }
+}
+
+pub fn detect_hash_algorithm<S: AsRef<str>>(hash: S) -> Result<Vec<SupportedAlgorithm>, String> {
+    let hash = hash.as_ref();
+
+    if is_decimal_checksum(hash) {
+        return Ok(vec![
+            SupportedAlgorithm::CKSUM,
+            SupportedAlgorithm::BSDSUM,
+            SupportedAlgorithm::SYSVSUM,
+        ]);
+    }
+
+    if let Some(algorithms) = detect_hex_hash_algorithm(hash) {
+        return Ok(algorithms);
+    }
+
+    // Not hex, so go by the digest length it decodes to as base64, base32 or Nix base32.
+    let mut algorithms = Vec::new();
+    for bytes in decode_hash(hash) {
+        for algorithm in detect_hex_hash_algorithm(&bytes_to_hex(&bytes)).unwrap_or_default() {
+            if !algorithms.contains(&algorithm) {
+                algorithms.push(algorithm);
+            }
+        }
+    }
+
+    if algorithms.is_empty() {
+        Err(String::from("Error: Invalid hash."))
+    } else {
+        Ok(algorithms)
     }
 }
 
@@ -99,11 +446,234 @@ mod test_extra {
         );
     }
 
+    #[test]
+    fn test_detect_hash_algorithm_lists_blake3_for_256_bit_hashes() {
+        assert!(detect_hash_algorithm(
+            "00691413c731ee37f551bfaca6a34b8443b3e85d7c0816a6fe90aa8fc8eaec95"
+        )
+        .unwrap()
+        .contains(&SupportedAlgorithm::BLAKE3));
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_detect_hash_algorithm_lists_sha3_next_to_sha2() {
+        assert_eq!(
+            detect_hash_algorithm(
+                "79a8a4b5452637776f4cfdb72598272abcd0b697b3a62fb7380021e1a3dc0e2139e3035ac9d938a17bf50be333ebd045"
+            )
+            .unwrap(),
+            vec![SupportedAlgorithm::SHA384, SupportedAlgorithm::SHA3_384]
+        );
+    }
+
+    #[test]
+    fn test_detect_hash_algorithm_decimal_checksum() {
+        assert_eq!(
+            detect_hash_algorithm("02698     1").unwrap(),
+            vec![
+                SupportedAlgorithm::CKSUM,
+                SupportedAlgorithm::BSDSUM,
+                SupportedAlgorithm::SYSVSUM
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hash_matches_decimal_checksum_ignores_padding() {
+        assert!(hash_matches(
+            SupportedAlgorithm::BSDSUM,
+            "02698     1",
+            "2698 1"
+        ));
+        assert!(!hash_matches(
+            SupportedAlgorithm::CKSUM,
+            "2941961746 16",
+            "2941961746 17"
+        ));
+    }
+
+    #[test]
+    fn test_hex_to_bytes() {
+        assert_eq!(hex_to_bytes("00ff7A").unwrap(), vec![0x00, 0xff, 0x7a]);
+        assert!(hex_to_bytes("0f0").is_err());
+        assert!(hex_to_bytes("zz").is_err());
+    }
+
     #[test]
     fn test_detect_hash_algorithm_xxhash64() {
         assert_eq!(
             detect_hash_algorithm("4a34911ba20e6c30").unwrap(),
-            vec![SupportedAlgorithm::XXHASH64]
+            vec![SupportedAlgorithm::XXHASH64, SupportedAlgorithm::CRC64]
+        );
+    }
+
+    #[test]
+    fn test_detect_hash_algorithm_lists_crc32_next_to_xxhash32() {
+        assert_eq!(
+            detect_hash_algorithm("cbf43926").unwrap(),
+            vec![
+                SupportedAlgorithm::XXHASH32,
+                SupportedAlgorithm::CRC32,
+                SupportedAlgorithm::CRC32C
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_hash_algorithm_xxhash3_128() {
+        assert_eq!(
+            detect_hash_algorithm("XXH3_39b4146c8a787726798df846da630b0d").unwrap(),
+            vec![SupportedAlgorithm::XXHASH3_128]
         );
+        assert!(detect_hash_algorithm("39b4146c8a787726798df846da630b0d")
+            .unwrap()
+            .contains(&SupportedAlgorithm::XXHASH3_128));
+    }
+
+    #[test]
+    fn test_hash_matches_xxh3_with_or_without_prefix() {
+        assert!(hash_matches(
+            SupportedAlgorithm::XXHASH3_128,
+            "XXH3_39b4146c8a787726798df846da630b0d",
+            "39B4146C8A787726798DF846DA630B0D"
+        ));
+        assert!(!hash_matches(
+            SupportedAlgorithm::SHA256,
+            "XXH3_39b4146c8a787726798df846da630b0d",
+            "39b4146c8a787726798df846da630b0d"
+        ));
+    }
+
+    #[test]
+    fn test_parse_seed() {
+        assert_eq!(parse_seed("42").unwrap(), 42);
+        assert_eq!(parse_seed("0x2A").unwrap(), 42);
+        assert!(parse_seed("-1").is_err());
+    }
+
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_detect_hash_algorithm_lists_regional_hashes() {
+        assert_eq!(
+            detect_hash_algorithm("8eb208f7e05d987a9b044a8e98c6b087f15a0bfc").unwrap(),
+            vec![SupportedAlgorithm::SHA1, SupportedAlgorithm::RIPEMD160]
+        );
+
+        let detected = detect_hash_algorithm(
+            "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0",
+        )
+        .unwrap();
+        assert!(detected.contains(&SupportedAlgorithm::SM3));
+        assert!(detected.contains(&SupportedAlgorithm::STREEBOG256));
+
+        assert!(detect_hash_algorithm(
+            "1b54d01a4af5b9d5cc3d86d68d285462b19abc2475222f35c085122be4ba1ffa00ad30f8767b3a82384c6574f024c311e2a481332b08ef7f41797891c1646f48"
+        )
+        .unwrap()
+        .contains(&SupportedAlgorithm::STREEBOG512));
+    }
+
+    // SHA256 of the empty string.
+    const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    #[test]
+    fn test_output_encoding_encode() {
+        let bytes = hex_to_bytes(EMPTY_SHA256).unwrap();
+        assert_eq!(
+            OutputEncoding::Base64.encode(&bytes),
+            "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+        );
+        assert_eq!(
+            OutputEncoding::Base64Url.encode(&bytes),
+            "47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU"
+        );
+        assert_eq!(
+            OutputEncoding::Base32.encode(&bytes),
+            "4OYMIQUY7QOBJGX36TEJS35ZEQT24QPEMSNZGTFESWMRW6CSXBKQ===="
+        );
+        assert_eq!(
+            OutputEncoding::Nix32.encode(&bytes),
+            "0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73"
+        );
+    }
+
+    #[test]
+    fn test_encode_hash_keeps_xxh3_prefix_in_hex_only() {
+        let hash = "XXH3_2d06800538d394c2";
+        assert_eq!(
+            encode_hash(
+                SupportedAlgorithm::XXHASH3_64,
+                hash,
+                OutputEncoding::UpperHex
+            )
+            .unwrap(),
+            "XXH3_2D06800538D394C2"
+        );
+        let base64 =
+            encode_hash(SupportedAlgorithm::XXHASH3_64, hash, OutputEncoding::Base64).unwrap();
+        assert_eq!(base64, "LQaABTjTlMI=");
+        assert!(hash_matches(SupportedAlgorithm::XXHASH3_64, hash, &base64));
+    }
+
+    #[test]
+    fn test_output_encoding_from_input_distinguishes_hex_case() {
+        assert_eq!(
+            OutputEncoding::from_input("hex").unwrap(),
+            OutputEncoding::Hex
+        );
+        assert_eq!(
+            OutputEncoding::from_input("HEX").unwrap(),
+            OutputEncoding::UpperHex
+        );
+        assert_eq!(
+            OutputEncoding::from_input("Hex").unwrap(),
+            OutputEncoding::Hex
+        );
+        assert_eq!(
+            OutputEncoding::from_input("Nix-Base32").unwrap(),
+            OutputEncoding::Nix32
+        );
+        assert!(OutputEncoding::from_input("base58").is_err());
+    }
+
+    #[test]
+    fn test_hash_matches_decodes_expected_hash() {
+        for expected in [
+            "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU",
+            "47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU=",
+            "4oymiquy7qobjgx36tejs35zeqt24qpemsnzgtfeswmrw6csxbkq",
+            "0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73",
+        ] {
+            assert!(hash_matches(
+                SupportedAlgorithm::SHA256,
+                EMPTY_SHA256,
+                expected
+            ));
+        }
+        assert!(!hash_matches(
+            SupportedAlgorithm::SHA256,
+            EMPTY_SHA256,
+            "0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c74"
+        ));
+    }
+
+    #[test]
+    fn test_detect_hash_algorithm_by_decoded_length() {
+        assert_eq!(
+            detect_hash_algorithm("47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=").unwrap(),
+            detect_hash_algorithm(EMPTY_SHA256).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_escape_file_name_round_trips() {
+        assert_eq!(escape_file_name("plain name.txt"), None);
+
+        let name = "a\\b\nc\rd.txt";
+        let escaped = escape_file_name(name).unwrap();
+        assert_eq!(escaped, "a\\\\b\\nc\\rd.txt");
+        assert_eq!(unescape_file_name(&escaped).unwrap(), name);
+        assert!(unescape_file_name("a\\tb").is_err());
     }
 }
@@ -0,0 +1,346 @@
+use crate::{calculator, resolve_shasum_entry_path, Compare, Data};
+use std::fmt;
+use std::io::BufRead;
+use std::path::Path;
+
+pub const HASHDEEP_HEADER: &str = "%%%% HASHDEEP-1.0";
+const COLUMNS_PREFIX: &str = "%%%% ";
+const COMMENT_PREFIX: &str = "##";
+
+/*
+* A hashdeep file:
+      %%%% HASHDEEP-1.0
+      %%%% size,md5,sha256,filename
+      ## Invoked from: /home/user
+      ## $ hashdeep -c md5,sha256 -r photos
+      ##
+      2794,07c4e6a2c2db5f2d3a8998a3dba84a96,00691413...eaec95,/home/user/photos/滕王阁序.txt
+  The size column is optional, every other column but the last names a hash algorithm, and the
+  file name is everything after the last hash, commas included.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashdeepEntry {
+    pub size: Option<u64>,
+    // One per algorithm of the file, in the same order.
+    pub hashes: Vec<String>,
+    pub file_name: String,
+}
+
+// Comments are kept in place, so a parsed file is written back with its invocation header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HashdeepLine {
+    Comment(String),
+    Entry(HashdeepEntry),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashdeepFile {
+    pub has_size: bool,
+    pub algorithms: Vec<calculator::SupportedAlgorithm>,
+    pub lines: Vec<HashdeepLine>,
+    // Line number and error of every line that is neither a comment nor a valid entry.
+    pub malformed: Vec<(usize, String)>,
+}
+
+impl HashdeepFile {
+    pub fn new(has_size: bool, algorithms: Vec<calculator::SupportedAlgorithm>) -> HashdeepFile {
+        Self {
+            has_size,
+            algorithms,
+            lines: Vec::new(),
+            malformed: Vec::new(),
+        }
+    }
+
+    // The comments hashdeep starts its output with.
+    pub fn with_invocation<S: AsRef<str>>(
+        has_size: bool,
+        algorithms: Vec<calculator::SupportedAlgorithm>,
+        directory: S,
+        command: S,
+    ) -> HashdeepFile {
+        let mut file = Self::new(has_size, algorithms);
+        file.lines.extend([
+            HashdeepLine::Comment(format!("## Invoked from: {}", directory.as_ref())),
+            HashdeepLine::Comment(format!("## $ {}", command.as_ref())),
+            HashdeepLine::Comment(String::from("##")),
+        ]);
+        file
+    }
+
+    // Only an invalid header fails the whole file, invalid entries are collected in `malformed`.
+    pub fn parse<R: BufRead>(reader: R) -> Result<HashdeepFile, String> {
+        let mut lines = reader.lines();
+        let mut next_line = || {
+            lines
+                .next()
+                .transpose()
+                .map_err(|error| format!("Error: Cannot read hashdeep file: {}", error))
+        };
+
+        if next_line()?.as_deref().map(str::trim_end) != Some(HASHDEEP_HEADER) {
+            return Err("Error: Not a valid hashdeep file.".to_string());
+        }
+        let columns = next_line()?.unwrap_or_default();
+        let mut file = parse_columns(&columns)?;
+
+        // The header takes the first two lines.
+        let mut line_number = 2;
+        while let Some(line) = next_line()? {
+            line_number += 1;
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with(COMMENT_PREFIX) {
+                file.lines.push(HashdeepLine::Comment(line.to_string()));
+                continue;
+            }
+            match file.parse_entry(line) {
+                Ok(entry) => file.lines.push(HashdeepLine::Entry(entry)),
+                Err(error) => file.malformed.push((line_number, error)),
+            }
+        }
+
+        Ok(file)
+    }
+
+    fn parse_entry(&self, line: &str) -> Result<HashdeepEntry, String> {
+        let invalid = || format!("Error: Not a valid hashdeep line: {}", line);
+        let leading = usize::from(self.has_size) + self.algorithms.len();
+        let mut fields = line.splitn(leading + 1, ',');
+
+        let size = match self.has_size {
+            true => Some(
+                fields
+                    .next()
+                    .and_then(|size| size.trim().parse().ok())
+                    .ok_or_else(invalid)?,
+            ),
+            false => None,
+        };
+        let hashes: Vec<String> = fields
+            .by_ref()
+            .take(self.algorithms.len())
+            .map(|hash| hash.trim().to_string())
+            .collect();
+        let file_name = fields.next().ok_or_else(invalid)?;
+        if hashes.len() != self.algorithms.len() || hashes.iter().any(String::is_empty) {
+            return Err(invalid());
+        }
+
+        Ok(HashdeepEntry {
+            size,
+            hashes,
+            file_name: file_name.to_string(),
+        })
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &HashdeepEntry> {
+        self.lines.iter().filter_map(|line| match line {
+            HashdeepLine::Entry(entry) => Some(entry),
+            HashdeepLine::Comment(_) => None,
+        })
+    }
+
+    /*
+    * One Compare per file checking the size and every digest in a single read. With `algorithm`
+      only that column is checked (and the size). Relative names are resolved against `base_dir`.
+    */
+    pub fn compare_tasks(
+        &self,
+        base_dir: &Path,
+        algorithm: Option<calculator::SupportedAlgorithm>,
+        options: &calculator::HashOptions,
+    ) -> Result<Vec<Compare>, String> {
+        if let Some(algorithm) = algorithm {
+            if !self.algorithms.contains(&algorithm) {
+                return Err(format!(
+                    "Error: The hashdeep file has no {} column.",
+                    algorithm
+                ));
+            }
+        }
+
+        Ok(self
+            .entries()
+            .map(|entry| {
+                let digests = self
+                    .algorithms
+                    .iter()
+                    .zip(&entry.hashes)
+                    .filter(|(column, _)| algorithm.is_none_or(|algorithm| algorithm == **column))
+                    .map(|(column, hash)| (*column, hash.clone()))
+                    .collect();
+                Compare::with_digests(
+                    Data::ReadFile(resolve_shasum_entry_path(base_dir, &entry.file_name)),
+                    digests,
+                    entry.size,
+                    options.clone(),
+                )
+            })
+            .collect())
+    }
+}
+
+// "%%%% size,md5,sha256,filename"
+fn parse_columns(line: &str) -> Result<HashdeepFile, String> {
+    let columns = line
+        .trim_end()
+        .strip_prefix(COLUMNS_PREFIX)
+        .ok_or_else(|| "Error: Not a valid hashdeep file.".to_string())?;
+    let mut columns: Vec<&str> = columns.split(',').map(str::trim).collect();
+
+    if columns.pop() != Some("filename") {
+        return Err("Error: The last hashdeep column must be filename.".to_string());
+    }
+    let has_size = columns.first() == Some(&"size");
+    if has_size {
+        columns.remove(0);
+    }
+
+    let algorithms = columns
+        .iter()
+        .map(|column| {
+            calculator::SupportedAlgorithm::from_input(column)
+                .map_err(|_| format!("Error: Unsupported hashdeep column: {}.", column))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if algorithms.is_empty() {
+        return Err("Error: The hashdeep file lists no hash column.".to_string());
+    }
+
+    Ok(HashdeepFile::new(has_size, algorithms))
+}
+
+impl fmt::Display for HashdeepFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut columns: Vec<String> = Vec::new();
+        if self.has_size {
+            columns.push(String::from("size"));
+        }
+        columns.extend(
+            self.algorithms
+                .iter()
+                .map(|algorithm| algorithm.prefixed_hash_name()),
+        );
+        columns.push(String::from("filename"));
+
+        writeln!(f, "{}", HASHDEEP_HEADER)?;
+        writeln!(f, "{}{}", COLUMNS_PREFIX, columns.join(","))?;
+        for line in &self.lines {
+            match line {
+                HashdeepLine::Comment(comment) => writeln!(f, "{}", comment)?,
+                HashdeepLine::Entry(entry) => writeln!(f, "{}", entry)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for HashdeepEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(size) = self.size {
+            write!(f, "{},", size)?;
+        }
+        for hash in &self.hashes {
+            write!(f, "{},", hash)?;
+        }
+        write!(f, "{}", self.file_name)
+    }
+}
+
+#[cfg(test)]
+mod test_hashdeep {
+    use super::*;
+    use crate::calculator::SupportedAlgorithm;
+    use crate::{ComputeHash, IfMatch};
+
+    const SAMPLE: &str = "%%%% HASHDEEP-1.0
+%%%% size,sha256,blake3,filename
+## Invoked from: /home/user
+## $ hashdeep -c sha256 -r tests
+##
+2794,00691413c731ee37f551bfaca6a34b8443b3e85d7c0816a6fe90aa8fc8eaec95,5e4a1e4e3b3ba4f5a61eaf9f3b5e7b4c1e5ab0d7fb0d2f7bb0b2b3c4b5e3f3f1,滕王阁序.txt
+";
+
+    #[test]
+    fn test_hashdeep_round_trip_keeps_comments() {
+        let file = HashdeepFile::parse(SAMPLE.as_bytes()).unwrap();
+
+        assert!(file.has_size);
+        assert_eq!(
+            file.algorithms,
+            [SupportedAlgorithm::SHA256, SupportedAlgorithm::BLAKE3]
+        );
+        assert_eq!(file.entries().count(), 1);
+        assert_eq!(file.to_string(), SAMPLE);
+    }
+
+    #[test]
+    fn test_hashdeep_file_names_may_contain_commas() {
+        let file = HashdeepFile::parse(
+            "%%%% HASHDEEP-1.0\n%%%% sha256,filename\nabcd,a, b.txt\n".as_bytes(),
+        )
+        .unwrap();
+
+        let entry = file.entries().next().unwrap();
+        assert_eq!(entry.size, None);
+        assert_eq!(entry.file_name, "a, b.txt");
+    }
+
+    #[test]
+    fn test_hashdeep_keeps_entries_around_malformed_lines() {
+        let file = HashdeepFile::parse(
+            "%%%% HASHDEEP-1.0\n%%%% size,sha256,filename\nx,abcd,a.txt\n5,abcd,b.txt\n5,c.txt\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(file.entries().count(), 1);
+        let lines: Vec<usize> = file.malformed.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, [3, 5]);
+    }
+
+    #[test]
+    fn test_hashdeep_rejects_unknown_columns() {
+        assert!(
+            HashdeepFile::parse("%%%% HASHDEEP-1.0\n%%%% size,tiger,filename\n".as_bytes())
+                .is_err()
+        );
+        assert!(HashdeepFile::parse("%%%% size,sha256,filename\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_hashdeep_checks_size_and_every_digest() {
+        let hash = |text: &str| {
+            let sha256 = Data::Text(text.to_string())
+                .compute_hash(SupportedAlgorithm::SHA256)
+                .unwrap();
+            let blake3 = Data::Text(text.to_string())
+                .compute_hash(SupportedAlgorithm::BLAKE3)
+                .unwrap();
+            vec![
+                (SupportedAlgorithm::SHA256, sha256),
+                (SupportedAlgorithm::BLAKE3, blake3),
+            ]
+        };
+        let options = calculator::HashOptions::default();
+        let text = || Data::Text(String::from("Hello"));
+
+        let task = Compare::with_digests(text(), hash("Hello"), Some(5), options.clone());
+        assert_eq!(task.compute().unwrap(), IfMatch::Match(String::new()));
+
+        let task = Compare::with_digests(text(), hash("Hello"), Some(6), options.clone());
+        assert_eq!(task.compute().unwrap(), IfMatch::Failed(String::new()));
+
+        let mut digests = hash("Hello");
+        digests[1] = hash("Bye").remove(1);
+        let task = Compare::with_digests(text(), digests, Some(5), options);
+        let IfMatch::Failed(message) = task.compute().unwrap() else {
+            panic!("a wrong second digest must fail");
+        };
+        assert!(message.contains("BLAKE3 FAILED"));
+        assert!(!message.contains("SHA256"));
+    }
+}
@@ -0,0 +1,439 @@
+use crate::calculator::BUFFER_SIZE;
+use crate::Data;
+use std::fmt;
+use std::fs::{File, Metadata};
+use std::io::{stdin, BufRead, BufReader, Error, ErrorKind, Read};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread;
+
+// Reads of large files go through buffers of this size.
+pub const LARGE_BUFFER_SIZE: usize = 1024 * 1024;
+// Auto pipelines reads from this size on.
+pub const LARGE_FILE_THRESHOLD: u64 = 1024 * 1024;
+// A mapped file is handed to the hashers in slices of this size, so several hashers share the cache.
+const MMAP_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+// Buffers in flight between the reader thread and the hashers in pipelined mode.
+pub const PIPELINE_BUFFERS: usize = 4;
+// Pages already hashed are dropped from the page cache every this many bytes.
+const DROP_BEHIND_SIZE: u64 = 64 * 1024 * 1024;
+
+/*
+* How a file is read. Standard is the plain BUFFER_SIZE reader. Buffered reads LARGE_BUFFER_SIZE
+  at a time, Pipelined does the same on a reader thread while the data read before is hashed,
+  and Mmap maps the whole file. When chosen explicitly, all but Standard tell the kernel the file
+  is read sequentially and drop the pages they have hashed, so scrubbing a large tree does not
+  evict the page cache. Auto picks Standard or Pipelined by file size and leaves the page cache
+  alone, as a file hashed right after it was written should stay cached. Mmap is only used when
+  asked for: a mapped file
+  that is truncated while it is hashed kills the process with SIGBUS instead of failing a read.
+  Pipes, devices and stdin are never mapped.
+*/
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum IoStrategy {
+    #[default]
+    Auto,
+    Standard,
+    Buffered,
+    Pipelined,
+    Mmap,
+}
+
+impl IoStrategy {
+    pub fn from_input<S: AsRef<str>>(input: S) -> Result<IoStrategy, String> {
+        match input.as_ref().trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(IoStrategy::Auto),
+            "standard" => Ok(IoStrategy::Standard),
+            "buffered" => Ok(IoStrategy::Buffered),
+            "pipelined" => Ok(IoStrategy::Pipelined),
+            "mmap" => Ok(IoStrategy::Mmap),
+            other => Err(format!(
+                "Error: Unsupported I/O strategy: {} (expected auto, standard, buffered, pipelined or mmap).",
+                other
+            )),
+        }
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            IoStrategy::Auto => "auto",
+            IoStrategy::Standard => "standard",
+            IoStrategy::Buffered => "buffered",
+            IoStrategy::Pipelined => "pipelined",
+            IoStrategy::Mmap => "mmap",
+        }
+    }
+
+    // The strategy actually used for a file, Auto and impossible choices resolved.
+    pub fn resolve(self, metadata: &Metadata) -> IoStrategy {
+        let regular = metadata.is_file();
+        match self {
+            IoStrategy::Auto if !regular || metadata.len() >= LARGE_FILE_THRESHOLD => {
+                IoStrategy::Pipelined
+            }
+            IoStrategy::Auto => IoStrategy::Standard,
+            // Only non-empty regular files can be mapped.
+            IoStrategy::Mmap if !regular || metadata.len() == 0 => IoStrategy::Buffered,
+            strategy => strategy,
+        }
+    }
+}
+
+impl fmt::Display for IoStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn advise(file: &File, offset: u64, len: u64, advice: libc::c_int) {
+    use std::os::unix::io::AsRawFd;
+
+    // Only a hint, failures are harmless.
+    unsafe {
+        libc::posix_fadvise(
+            file.as_raw_fd(),
+            offset as libc::off_t,
+            len as libc::off_t,
+            advice,
+        );
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn advise_sequential(file: &File) {
+    advise(file, 0, 0, libc::POSIX_FADV_SEQUENTIAL);
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn drop_cached(file: &File, offset: u64, len: u64) {
+    advise(file, offset, len, libc::POSIX_FADV_DONTNEED);
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+fn advise_sequential(_file: &File) {}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+fn drop_cached(_file: &File, _offset: u64, _len: u64) {}
+
+// A file read sequentially, dropping the pages already read from the page cache.
+struct DropBehind {
+    file: File,
+    position: u64,
+    dropped: u64,
+}
+
+impl DropBehind {
+    fn new(file: File) -> Self {
+        advise_sequential(&file);
+        Self {
+            file,
+            position: 0,
+            dropped: 0,
+        }
+    }
+}
+
+impl Read for DropBehind {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let read_bytes = self.file.read(buf)?;
+        self.position += read_bytes as u64;
+
+        if self.position - self.dropped >= DROP_BEHIND_SIZE {
+            drop_cached(&self.file, self.dropped, self.position - self.dropped);
+            self.dropped = self.position;
+        }
+        Ok(read_bytes)
+    }
+}
+
+impl Drop for DropBehind {
+    fn drop(&mut self) {
+        drop_cached(&self.file, self.dropped, 0);
+    }
+}
+
+/*
+* Reads on a separate thread into a ring of `buffers` buffers while the caller hashes the ones
+  already filled, so a slow disk or network filesystem and the CPU work at the same time.
+  The thread stops at the end of the input, on the first error, or when the reader is dropped.
+*/
+pub struct PipelinedReader {
+    filled: Receiver<Result<Vec<u8>, Error>>,
+    empty: SyncSender<Vec<u8>>,
+    current: Vec<u8>,
+    position: usize,
+}
+
+impl PipelinedReader {
+    pub fn new<R: Read + Send + 'static>(reader: R) -> Self {
+        Self::with_buffers(reader, PIPELINE_BUFFERS, LARGE_BUFFER_SIZE)
+    }
+
+    pub fn with_buffers<R: Read + Send + 'static>(
+        mut reader: R,
+        buffers: usize,
+        buffer_size: usize,
+    ) -> Self {
+        let buffers = buffers.max(1);
+        let buffer_size = buffer_size.max(1);
+        let (filled_sender, filled) = sync_channel(buffers);
+        let (empty, empty_receiver) = sync_channel::<Vec<u8>>(buffers);
+        for _ in 0..buffers {
+            let _ = empty.send(Vec::with_capacity(buffer_size));
+        }
+
+        thread::spawn(move || {
+            while let Ok(mut buffer) = empty_receiver.recv() {
+                buffer.resize(buffer_size, 0);
+                let mut read_bytes = 0;
+                while read_bytes < buffer_size {
+                    match reader.read(&mut buffer[read_bytes..]) {
+                        Ok(0) => break,
+                        Ok(count) => read_bytes += count,
+                        Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                        Err(error) => {
+                            let _ = filled_sender.send(Err(error));
+                            return;
+                        }
+                    }
+                }
+
+                if read_bytes == 0 {
+                    return;
+                }
+                buffer.truncate(read_bytes);
+                if filled_sender.send(Ok(buffer)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            filled,
+            empty,
+            current: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl Read for PipelinedReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let available = self.fill_buf()?;
+        let read_bytes = available.len().min(buf.len());
+        buf[..read_bytes].copy_from_slice(&available[..read_bytes]);
+        self.consume(read_bytes);
+        Ok(read_bytes)
+    }
+}
+
+impl BufRead for PipelinedReader {
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        if self.position == self.current.len() {
+            let used = std::mem::take(&mut self.current);
+            if used.capacity() > 0 {
+                let _ = self.empty.send(used);
+            }
+            self.position = 0;
+
+            match self.filled.recv() {
+                Ok(buffer) => self.current = buffer?,
+                // The reader thread has reached the end of the input.
+                Err(_) => return Ok(&[]),
+            }
+        }
+        Ok(&self.current[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.current.len());
+    }
+}
+
+// Hands a mapped file out in MMAP_CHUNK_SIZE slices.
+struct SliceChunks<'a> {
+    data: &'a [u8],
+}
+
+impl Read for SliceChunks<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.data.read(buf)
+    }
+}
+
+impl BufRead for SliceChunks<'_> {
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        Ok(&self.data[..self.data.len().min(MMAP_CHUNK_SIZE)])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.data = &self.data[amount.min(self.data.len())..];
+    }
+}
+
+fn read_mapped<T>(file: File, consume: impl FnOnce(&mut dyn BufRead) -> T) -> Result<T, Error> {
+    // SAFETY: the map is only read while it is alive. Like any reader, a file truncated by
+    // another process while it is hashed gives a wrong result (or SIGBUS), which `--io buffered`
+    // avoids.
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    #[cfg(unix)]
+    let _ = map.advise(memmap2::Advice::Sequential);
+
+    let result = consume(&mut SliceChunks { data: &map });
+    drop(map);
+    drop_cached(&file, 0, 0);
+    Ok(result)
+}
+
+// Passes a reader for `file` using the I/O strategy chosen for it to `consume`.
+pub fn read_file<T>(
+    file: File,
+    strategy: IoStrategy,
+    consume: impl FnOnce(&mut dyn BufRead) -> T,
+) -> Result<T, Error> {
+    match strategy.resolve(&file.metadata()?) {
+        IoStrategy::Mmap => read_mapped(file, consume),
+        IoStrategy::Buffered => Ok(consume(&mut BufReader::with_capacity(
+            LARGE_BUFFER_SIZE,
+            DropBehind::new(file),
+        ))),
+        // Auto leaves the page cache alone, only an explicit choice drops what was read.
+        IoStrategy::Pipelined if strategy == IoStrategy::Auto => {
+            Ok(consume(&mut PipelinedReader::new(file)))
+        }
+        IoStrategy::Pipelined => Ok(consume(&mut PipelinedReader::new(DropBehind::new(file)))),
+        _ => Ok(consume(&mut BufReader::with_capacity(BUFFER_SIZE, file))),
+    }
+}
+
+// Standard input cannot be mapped, Auto pipelines it.
+pub fn read_stdin<T>(strategy: IoStrategy, consume: impl FnOnce(&mut dyn BufRead) -> T) -> T {
+    match strategy {
+        IoStrategy::Standard => consume(&mut stdin().lock()),
+        IoStrategy::Buffered | IoStrategy::Mmap => consume(&mut BufReader::with_capacity(
+            LARGE_BUFFER_SIZE,
+            stdin().lock(),
+        )),
+        IoStrategy::Auto | IoStrategy::Pipelined => consume(&mut PipelinedReader::new(stdin())),
+    }
+}
+
+/*
+* Contents of the small regular files among `data`, read in batches with io_uring, None for the
+  rest. None altogether when io_uring is not compiled in or not available, or the strategy is
+  not Auto.
+*/
+pub fn read_small_files(data: &[&Data], strategy: IoStrategy) -> Option<Vec<Option<Vec<u8>>>> {
+    #[cfg(all(target_os = "linux", feature = "io_uring"))]
+    if strategy == IoStrategy::Auto {
+        let (indices, paths): (Vec<usize>, Vec<&str>) = data
+            .iter()
+            .enumerate()
+            .filter_map(|(index, data)| match data {
+                Data::ReadFile(path) if path != "-" => Some((index, path.as_str())),
+                _ => None,
+            })
+            .unzip();
+
+        let mut contents = vec![None; data.len()];
+        for (index, file) in indices
+            .into_iter()
+            .zip(crate::uring::read_small_files(&paths)?)
+        {
+            contents[index] = file;
+        }
+        return Some(contents);
+    }
+
+    let _ = (data, strategy);
+    None
+}
+
+#[cfg(test)]
+mod test_input {
+    use super::*;
+    use crate::calculator::{hash_calculator, SupportedAlgorithm};
+
+    #[test]
+    fn test_io_strategies_agree() {
+        let expected = "00691413c731ee37f551bfaca6a34b8443b3e85d7c0816a6fe90aa8fc8eaec95";
+        for strategy in [
+            IoStrategy::Auto,
+            IoStrategy::Standard,
+            IoStrategy::Buffered,
+            IoStrategy::Pipelined,
+            IoStrategy::Mmap,
+        ] {
+            let file = File::open("tests/滕王阁序.txt").unwrap();
+            let hash = read_file(file, strategy, |reader| {
+                hash_calculator(reader, SupportedAlgorithm::SHA256)
+            })
+            .unwrap()
+            .unwrap();
+            assert_eq!(hash, expected, "{}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_large_reads_cross_buffer_boundaries() {
+        let path = std::env::temp_dir().join(format!("ezcheck-input-{}", std::process::id()));
+        let data = crate::bench::synthetic_data(LARGE_BUFFER_SIZE * 2 + 123);
+        std::fs::write(&path, &data).unwrap();
+
+        let expected = hash_calculator(&data[..], SupportedAlgorithm::CRC32).unwrap();
+        for strategy in [
+            IoStrategy::Auto,
+            IoStrategy::Buffered,
+            IoStrategy::Pipelined,
+            IoStrategy::Mmap,
+        ] {
+            let file = File::open(&path).unwrap();
+            let hash = read_file(file, strategy, |reader| {
+                hash_calculator(reader, SupportedAlgorithm::CRC32)
+            })
+            .unwrap()
+            .unwrap();
+            assert_eq!(hash, expected, "{}", strategy);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pipelined_reader_matches_direct_read() {
+        let data = crate::bench::synthetic_data(100_003);
+        let expected = hash_calculator(&data[..], SupportedAlgorithm::SHA256).unwrap();
+
+        let reader = PipelinedReader::with_buffers(std::io::Cursor::new(data), 2, 4096);
+        assert_eq!(
+            hash_calculator(reader, SupportedAlgorithm::SHA256).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_pipelined_reader_reports_read_errors() {
+        struct FailingReader(usize);
+
+        impl Read for FailingReader {
+            fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+                if self.0 == 0 {
+                    return Err(Error::other("disk on fire"));
+                }
+                let count = self.0.min(buf.len());
+                buf[..count].fill(0);
+                self.0 -= count;
+                Ok(count)
+            }
+        }
+
+        let reader = PipelinedReader::with_buffers(FailingReader(10_000), 2, 1024);
+        let error = hash_calculator(reader, SupportedAlgorithm::CRC32).unwrap_err();
+        assert_eq!(error.to_string(), "disk on fire");
+    }
+
+    #[test]
+    fn test_io_strategy_from_input() {
+        assert_eq!(IoStrategy::from_input("MMAP").unwrap(), IoStrategy::Mmap);
+        assert!(IoStrategy::from_input("direct").is_err());
+    }
+}
@@ -0,0 +1,409 @@
+use crate::input::{read_small_files, IoStrategy};
+use crate::Data;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Condvar, Mutex};
+use std::thread;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JobOptions {
+    // Worker threads, 0 means one per available CPU.
+    pub jobs: usize,
+    // Most files hashed at once from the same device, unlimited when None.
+    pub per_device: Option<usize>,
+    // Report results as they finish instead of in input order.
+    pub unordered: bool,
+    // How run_file_jobs reads the files, see IoStrategy.
+    pub io: IoStrategy,
+}
+
+impl Default for JobOptions {
+    fn default() -> Self {
+        Self {
+            jobs: 1,
+            per_device: None,
+            unordered: false,
+            io: IoStrategy::Auto,
+        }
+    }
+}
+
+impl JobOptions {
+    pub fn worker_count(&self) -> usize {
+        match self.jobs {
+            0 => thread::available_parallelism().map_or(1, |count| count.get()),
+            jobs => jobs,
+        }
+    }
+}
+
+// Device a file lives on, so a spinning disk is not asked for many files at once.
+#[cfg(unix)]
+pub fn device_id(data: &Data) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    match data {
+        Data::ReadFile(path) if path != "-" => {
+            std::fs::metadata(path).ok().map(|metadata| metadata.dev())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn device_id(_data: &Data) -> Option<u64> {
+    None
+}
+
+struct DeviceLimiter {
+    limit: Option<usize>,
+    busy: Mutex<HashMap<u64, usize>>,
+    released: Condvar,
+}
+
+struct DevicePermit<'a> {
+    limiter: &'a DeviceLimiter,
+    device: u64,
+}
+
+impl DeviceLimiter {
+    fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit: limit.map(|limit| limit.max(1)),
+            busy: Mutex::new(HashMap::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    // `device` is only asked for with a limit, looking it up costs a stat() per file.
+    fn acquire<F: FnOnce() -> Option<u64>>(&self, device: F) -> Option<DevicePermit<'_>> {
+        let limit = self.limit?;
+        let device = device()?;
+        let mut busy = self.busy.lock().unwrap();
+        while busy.get(&device).copied().unwrap_or(0) >= limit {
+            busy = self.released.wait(busy).unwrap();
+        }
+        *busy.entry(device).or_insert(0) += 1;
+
+        Some(DevicePermit {
+            limiter: self,
+            device,
+        })
+    }
+}
+
+impl Drop for DevicePermit<'_> {
+    fn drop(&mut self) {
+        let mut busy = self.limiter.busy.lock().unwrap();
+        if let Some(count) = busy.get_mut(&self.device) {
+            *count -= 1;
+        }
+        self.limiter.released.notify_all();
+    }
+}
+
+/*
+* Runs `work` on every item `next` hands out with `workers` threads and passes each result to
+  `emit` on the calling thread with the index of its item, in index order unless `unordered` is
+  set. Indices run from 0 without gaps.
+*/
+fn run_pool<I, R, N, W, E>(workers: usize, unordered: bool, next: N, work: W, mut emit: E)
+where
+    R: Send,
+    N: Fn() -> Option<(usize, I)> + Sync,
+    W: Fn(I) -> R + Sync,
+    E: FnMut(usize, R),
+{
+    if workers <= 1 {
+        while let Some((index, item)) = next() {
+            emit(index, work(item));
+        }
+        return;
+    }
+
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let (next, work) = (&next, &work);
+            scope.spawn(move || {
+                while let Some((index, item)) = next() {
+                    if sender.send((index, work(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_to_emit = 0;
+        for (index, result) in receiver {
+            if unordered {
+                emit(index, result);
+                continue;
+            }
+
+            pending.insert(index, result);
+            while let Some(result) = pending.remove(&next_to_emit) {
+                emit(next_to_emit, result);
+                next_to_emit += 1;
+            }
+        }
+    });
+}
+
+/*
+* Runs `work` on every task with a pool of worker threads and hands each result to `emit` on the
+  calling thread, in task order unless `options.unordered` is set. `device` names the device a
+  task reads from for the per-device limit.
+*/
+pub fn run_jobs<T, R, D, W, E>(tasks: &[T], options: &JobOptions, device: D, work: W, mut emit: E)
+where
+    T: Sync,
+    R: Send,
+    D: Fn(&T) -> Option<u64> + Sync,
+    W: Fn(&T) -> R + Sync,
+    E: FnMut(&T, R),
+{
+    let workers = options.worker_count().min(tasks.len());
+    if workers <= 1 {
+        for task in tasks {
+            emit(task, work(task));
+        }
+        return;
+    }
+
+    let next_task = AtomicUsize::new(0);
+    let limiter = DeviceLimiter::new(options.per_device);
+    run_pool(
+        workers,
+        options.unordered,
+        || {
+            let index = next_task.fetch_add(1, Ordering::Relaxed);
+            tasks.get(index).map(|task| (index, task))
+        },
+        |task| {
+            let _permit = limiter.acquire(|| device(task));
+            work(task)
+        },
+        |index, result| emit(&tasks[index], result),
+    );
+}
+
+// Files per io_uring batch.
+const PREFETCH_FILES: usize = 256;
+// Batches read ahead of the workers at most, which bounds the memory held by read files.
+const PREFETCH_BATCHES: usize = 4;
+
+/*
+* Like run_jobs for tasks reading `data`. With the io_uring feature small files are read in
+  batches on a reader thread while the workers hash the batches read before, and `work` gets
+  their contents. Otherwise it gets None and reads the file itself.
+*/
+pub fn run_file_jobs<T, R, P, W, E>(
+    tasks: &[T],
+    options: &JobOptions,
+    data: P,
+    work: W,
+    mut emit: E,
+) where
+    T: Sync,
+    R: Send,
+    P: Fn(&T) -> &Data + Sync,
+    W: Fn(&T, Option<&[u8]>) -> R + Sync,
+    E: FnMut(&T, R),
+{
+    let read_batch = |batch: &[T]| {
+        let batch_data: Vec<&Data> = batch.iter().map(&data).collect();
+        read_small_files(&batch_data, options.io)
+    };
+
+    // The first batch tells whether io_uring is there at all.
+    let Some(first_contents) = read_batch(&tasks[..tasks.len().min(PREFETCH_FILES)]) else {
+        run_jobs(
+            tasks,
+            options,
+            |task| device_id(data(task)),
+            |task| work(task, None),
+            emit,
+        );
+        return;
+    };
+
+    let (sender, receiver) = mpsc::sync_channel(PREFETCH_FILES * PREFETCH_BATCHES);
+    let limiter = DeviceLimiter::new(options.per_device);
+
+    thread::scope(|scope| {
+        let read_batch = &read_batch;
+        scope.spawn(move || {
+            let mut first_contents = Some(first_contents);
+            for (batch_index, batch) in tasks.chunks(PREFETCH_FILES).enumerate() {
+                // A batch io_uring fails on is read by the workers.
+                let contents = first_contents
+                    .take()
+                    .or_else(|| read_batch(batch))
+                    .unwrap_or_else(|| vec![None; batch.len()]);
+                for (offset, item) in batch.iter().zip(contents).enumerate() {
+                    if sender
+                        .send((batch_index * PREFETCH_FILES + offset, item))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        });
+
+        // Dropped before the reader thread is joined, should a worker panic.
+        let receiver = Mutex::new(receiver);
+        run_pool(
+            options.worker_count().min(tasks.len()),
+            options.unordered,
+            || receiver.lock().unwrap().recv().ok(),
+            |(task, contents): (&T, Option<Vec<u8>>)| {
+                let _permit = limiter.acquire(|| device_id(data(task)));
+                work(task, contents.as_deref())
+            },
+            |index, result| emit(&tasks[index], result),
+        );
+    });
+}
+
+#[cfg(test)]
+mod test_jobs {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_run_jobs_keeps_input_order() {
+        let tasks: Vec<u64> = (0..32).collect();
+        let options = JobOptions {
+            jobs: 4,
+            ..JobOptions::default()
+        };
+        let mut results = Vec::new();
+
+        run_jobs(
+            &tasks,
+            &options,
+            |_| None,
+            |&task| {
+                // Later tasks finish first.
+                thread::sleep(Duration::from_millis(32 - task));
+                task * 2
+            },
+            |&task, result| results.push((task, result)),
+        );
+
+        let expected: Vec<(u64, u64)> = tasks.iter().map(|&task| (task, task * 2)).collect();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_run_jobs_unordered_reports_every_task() {
+        let tasks: Vec<u64> = (0..32).collect();
+        let options = JobOptions {
+            jobs: 4,
+            unordered: true,
+            ..JobOptions::default()
+        };
+        let mut results = Vec::new();
+
+        run_jobs(
+            &tasks,
+            &options,
+            |_| None,
+            |&task| task,
+            |_, result| results.push(result),
+        );
+
+        results.sort();
+        assert_eq!(results, tasks);
+    }
+
+    #[test]
+    fn test_run_jobs_looks_up_devices_only_with_a_limit() {
+        let tasks: Vec<u64> = (0..16).collect();
+        let options = JobOptions {
+            jobs: 4,
+            ..JobOptions::default()
+        };
+        let lookups = AtomicUsize::new(0);
+
+        run_jobs(
+            &tasks,
+            &options,
+            |_| {
+                lookups.fetch_add(1, Ordering::Relaxed);
+                Some(0)
+            },
+            |&task| task,
+            |_, _| {},
+        );
+        assert_eq!(lookups.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_run_jobs_respects_per_device_limit() {
+        let tasks: Vec<u64> = (0..16).collect();
+        let options = JobOptions {
+            jobs: 8,
+            per_device: Some(1),
+            ..JobOptions::default()
+        };
+        let running = AtomicUsize::new(0);
+        let most_running = AtomicUsize::new(0);
+
+        run_jobs(
+            &tasks,
+            &options,
+            |_| Some(1),
+            |&task| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most_running.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(5));
+                running.fetch_sub(1, Ordering::SeqCst);
+                task
+            },
+            |_, _| {},
+        );
+
+        assert_eq!(most_running.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_run_file_jobs_keeps_order_across_batches() {
+        let dir = std::env::temp_dir().join(format!("ezcheck-jobs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tasks: Vec<Data> = (0..PREFETCH_FILES * 2 + 17)
+            .map(|index| {
+                let path = dir.join(index.to_string());
+                std::fs::write(&path, index.to_string()).unwrap();
+                Data::ReadFile(path.to_string_lossy().into_owned())
+            })
+            .collect();
+        let options = JobOptions {
+            jobs: 4,
+            ..JobOptions::default()
+        };
+        let mut results = Vec::new();
+
+        run_file_jobs(
+            &tasks,
+            &options,
+            |task| task,
+            |task, contents| match (task, contents) {
+                (_, Some(contents)) => contents.to_vec(),
+                (Data::ReadFile(path), None) => std::fs::read(path).unwrap(),
+                _ => unreachable!(),
+            },
+            |_, result| results.push(String::from_utf8(result).unwrap()),
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let expected: Vec<String> = (0..tasks.len()).map(|index| index.to_string()).collect();
+        assert_eq!(results, expected);
+    }
+}
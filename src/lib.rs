@@ -4,47 +4,132 @@
     feature = "mix_backend"
 )))]
 compile_error!("You must enable at least one of the features: 'hashes_backend', 'ring_backend' or 'mix_backend'.");
-#[cfg(any(
-    all(feature = "hashes_backend", feature = "ring_backend"),
-    all(feature = "hashes_backend", feature = "mix_backend"),
-    all(feature = "ring_backend", feature = "mix_backend"),
-    all(
-        feature = "hashes_backend",
-        feature = "ring_backend",
-        feature = "mix_backend"
-    )
-))]
-compile_error!(
-    "Only one of the features `hashes_backend`, `ring_backend`, or `mix_backend` can be enabled at a time."
-);
 
+pub mod audit;
+pub mod bench;
 pub mod calculator;
 pub mod extra;
+pub mod hashdeep;
+pub mod input;
+pub mod jobs;
+pub mod selftest;
+pub mod sfv;
+pub mod tree;
+#[cfg(all(target_os = "linux", feature = "io_uring"))]
+pub mod uring;
+pub mod walk;
 
 use std::fmt;
-use std::fs::File;
-use std::io::{stdin, BufRead, BufReader};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 pub struct Calculate {
     data: Data,
     algorithm: calculator::SupportedAlgorithm,
+    options: calculator::HashOptions,
+    encoding: extra::OutputEncoding,
 }
 
 impl Calculate {
     pub fn new(data: Data, algorithm: calculator::SupportedAlgorithm) -> Calculate {
-        Self { data, algorithm }
+        Self::with_options(data, algorithm, calculator::HashOptions::default())
+    }
+
+    pub fn with_options(
+        data: Data,
+        algorithm: calculator::SupportedAlgorithm,
+        options: calculator::HashOptions,
+    ) -> Calculate {
+        Self::with_encoding(data, algorithm, options, extra::OutputEncoding::default())
+    }
+
+    pub fn with_encoding(
+        data: Data,
+        algorithm: calculator::SupportedAlgorithm,
+        options: calculator::HashOptions,
+        encoding: extra::OutputEncoding,
+    ) -> Calculate {
+        Self {
+            data,
+            algorithm,
+            options,
+            encoding,
+        }
     }
 
     pub fn compute(&self) -> Result<String, String> {
-        self.data.compute_hash(self.algorithm)
+        let hash = self
+            .data
+            .compute_hash_with_options(self.algorithm, &self.options)?;
+        extra::encode_hash(self.algorithm, &hash, self.encoding)
+    }
+}
+
+// Several algorithms over the same data, which is read only once.
+pub struct CalculateMulti {
+    data: Data,
+    algorithms: Vec<calculator::SupportedAlgorithm>,
+    options: calculator::HashOptions,
+    encoding: extra::OutputEncoding,
+}
+
+impl CalculateMulti {
+    pub fn new(data: Data, algorithms: Vec<calculator::SupportedAlgorithm>) -> CalculateMulti {
+        Self::with_encoding(
+            data,
+            algorithms,
+            calculator::HashOptions::default(),
+            extra::OutputEncoding::default(),
+        )
+    }
+
+    pub fn with_encoding(
+        data: Data,
+        algorithms: Vec<calculator::SupportedAlgorithm>,
+        options: calculator::HashOptions,
+        encoding: extra::OutputEncoding,
+    ) -> CalculateMulti {
+        Self {
+            data,
+            algorithms,
+            options,
+            encoding,
+        }
+    }
+
+    pub fn compute(&self) -> Result<Vec<(calculator::SupportedAlgorithm, String)>, String> {
+        self.compute_from(&self.data)
+    }
+
+    // Hashes `source` in place of the data, such as file contents read ahead in a batch.
+    pub fn compute_from<S: ComputeHash + ?Sized>(
+        &self,
+        source: &S,
+    ) -> Result<Vec<(calculator::SupportedAlgorithm, String)>, String> {
+        let hashes = source.compute_hashes_with_options(&self.algorithms, &self.options)?;
+
+        self.algorithms
+            .iter()
+            .zip(hashes)
+            .map(|(&algorithm, hash)| {
+                Ok((
+                    algorithm,
+                    extra::encode_hash(algorithm, &hash, self.encoding)?,
+                ))
+            })
+            .collect()
     }
 }
 
 pub struct Compare {
     pub data: Data,
     compare: String,
-    algorithm: calculator::SupportedAlgorithm,
+    algorithms: Vec<calculator::SupportedAlgorithm>,
+    options: calculator::HashOptions,
+    // Further digests and the size that must match as well, as hashdeep lists them.
+    required: Vec<(calculator::SupportedAlgorithm, String)>,
+    size: Option<u64>,
 }
 
 const ANSI_GREEN: &str = "\x1b[32m";
@@ -57,10 +142,55 @@ fn colorize(message: String, color: &str) -> String {
 
 impl Compare {
     pub fn new(data: Data, compare: String, algorithm: calculator::SupportedAlgorithm) -> Compare {
+        Self::with_options(data, compare, algorithm, calculator::HashOptions::default())
+    }
+
+    pub fn with_options(
+        data: Data,
+        compare: String,
+        algorithm: calculator::SupportedAlgorithm,
+        options: calculator::HashOptions,
+    ) -> Compare {
+        Self::with_candidates(data, compare, vec![algorithm], options)
+    }
+
+    // An ambiguous hash is checked against every candidate algorithm in one read of the data,
+    // so stdin and FIFOs can be verified too.
+    pub fn with_candidates(
+        data: Data,
+        compare: String,
+        algorithms: Vec<calculator::SupportedAlgorithm>,
+        options: calculator::HashOptions,
+    ) -> Compare {
         Self {
             data,
             compare,
-            algorithm,
+            algorithms,
+            options,
+            required: Vec::new(),
+            size: None,
+        }
+    }
+
+    // Every digest, and the size when given, has to match, all hashed in one read of the data.
+    pub fn with_digests(
+        data: Data,
+        digests: Vec<(calculator::SupportedAlgorithm, String)>,
+        size: Option<u64>,
+        options: calculator::HashOptions,
+    ) -> Compare {
+        let mut digests = digests.into_iter();
+        let (algorithms, compare) = match digests.next() {
+            Some((algorithm, compare)) => (vec![algorithm], compare),
+            None => (Vec::new(), String::new()),
+        };
+        Self {
+            data,
+            compare,
+            algorithms,
+            options,
+            required: digests.collect(),
+            size,
         }
     }
 
@@ -68,20 +198,122 @@ impl Compare {
         &self.compare
     }
 
+    pub fn algorithms(&self) -> &[calculator::SupportedAlgorithm] {
+        &self.algorithms
+    }
+
+    pub fn options(&self) -> &calculator::HashOptions {
+        &self.options
+    }
+
     pub fn compute(&self) -> Result<IfMatch, String> {
-        let hash_result = self.data.compute_hash(self.algorithm)?;
+        self.compute_from(&self.data)
+    }
+
+    pub fn compute_from<S: ComputeHash + ?Sized>(&self, source: &S) -> Result<IfMatch, String> {
+        if !self.required.is_empty() || self.size.is_some() {
+            return self.compute_all_from(source);
+        }
+
+        // Candidates that cannot take the given options (e.g. a key) are left out,
+        // unless none of them can.
+        let mut first_error = None;
+        let algorithms: Vec<calculator::SupportedAlgorithm> = self
+            .algorithms
+            .iter()
+            .copied()
+            .filter(
+                |&algorithm| match calculator::check_hash_options(algorithm, &self.options) {
+                    Ok(()) => true,
+                    Err(error) => {
+                        first_error.get_or_insert(error);
+                        false
+                    }
+                },
+            )
+            .collect();
+
+        if algorithms.is_empty() {
+            return Err(match first_error {
+                Some(error) => format!("Error: Error calculating hash: {}", error),
+                None => "Error: No hash algorithm to compare with.".to_string(),
+            });
+        }
+
+        let hash_results = source.compute_hashes_with_options(&algorithms, &self.options)?;
+
+        if let Some(algorithm) = algorithms
+            .iter()
+            .zip(&hash_results)
+            .find(|(&algorithm, hash)| extra::hash_matches(algorithm, hash, &self.compare))
+            .map(|(algorithm, _)| algorithm)
+        {
+            return Ok(IfMatch::Match(colorize(
+                format!("{} OK", algorithm),
+                ANSI_GREEN,
+            )));
+        }
 
-        if hash_result.eq_ignore_ascii_case(&self.compare) {
+        let failures: Vec<String> = algorithms
+            .iter()
+            .zip(&hash_results)
+            .map(|(algorithm, hash)| {
+                format!(
+                    "{}  Current Hash:{}",
+                    colorize(format!("{} FAILED", algorithm), ANSI_RED),
+                    hash
+                )
+            })
+            .collect();
+        Ok(IfMatch::Failed(failures.join("\n")))
+    }
+
+    fn compute_all_from<S: ComputeHash + ?Sized>(&self, source: &S) -> Result<IfMatch, String> {
+        let digests: Vec<(calculator::SupportedAlgorithm, &str)> = self
+            .algorithms
+            .iter()
+            .map(|&algorithm| (algorithm, self.compare.as_str()))
+            .chain(
+                self.required
+                    .iter()
+                    .map(|(algorithm, hash)| (*algorithm, hash.as_str())),
+            )
+            .collect();
+        let algorithms: Vec<calculator::SupportedAlgorithm> =
+            digests.iter().map(|(algorithm, _)| *algorithm).collect();
+        let hash_results = source.compute_hashes_with_options(&algorithms, &self.options)?;
+
+        let mut checked = Vec::new();
+        let mut failures = Vec::new();
+        if let Some(size) = self.size {
+            checked.push(String::from("size"));
+            match source.data_size()? {
+                Some(current) if current != size => failures.push(format!(
+                    "{}  Current Size:{}",
+                    colorize(String::from("size FAILED"), ANSI_RED),
+                    current
+                )),
+                _ => {}
+            }
+        }
+        for ((algorithm, expected), hash) in digests.iter().zip(&hash_results) {
+            checked.push(algorithm.to_string());
+            if !extra::hash_matches(*algorithm, hash, expected) {
+                failures.push(format!(
+                    "{}  Current Hash:{}",
+                    colorize(format!("{} FAILED", algorithm), ANSI_RED),
+                    hash
+                ));
+            }
+        }
+
+        if failures.is_empty() {
             Ok(IfMatch::Match(colorize(
-                format!("{} OK", self.algorithm),
+                format!("{} OK", checked.join(", ")),
                 ANSI_GREEN,
             )))
         } else {
-            Ok(IfMatch::Failed(format!(
-                "{}  Current Hash:{}",
-                colorize(format!("{} FAILED", self.algorithm), ANSI_RED),
-                hash_result
-            )))
+            Ok(IfMatch::Failed(failures.join("\n")))
         }
     }
 }
@@ -103,9 +335,14 @@ impl PartialEq for IfMatch {
 
 impl Eq for IfMatch {}
 
+#[derive(Clone)]
 pub enum Data {
     ReadFile(String),
     Text(String),
+    // A symbolic link, hashed by the path stored in it rather than by what it points to.
+    LinkTarget(String),
+    // A directory, hashed as a whole by tree::tree_hashes.
+    Tree(String, walk::WalkOptions),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -113,12 +350,15 @@ pub struct ResolvedHashInput {
     pub hash: String,
     pub algorithms: Vec<calculator::SupportedAlgorithm>,
     pub detected_from_hash: bool,
+    pub options: calculator::HashOptions,
 }
 
 impl fmt::Display for Data {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let value = match self {
-            Data::ReadFile(file_name) => file_name,
+            Data::ReadFile(file_name) | Data::LinkTarget(file_name) | Data::Tree(file_name, _) => {
+                file_name
+            }
             Data::Text(text) => text,
         };
         write!(f, "{}", value)
@@ -126,39 +366,151 @@ impl fmt::Display for Data {
 }
 
 pub trait ComputeHash {
-    fn compute_hash(&self, algorithm: calculator::SupportedAlgorithm) -> Result<String, String>;
+    // One hash per algorithm, in the same order, from a single pass over the data.
+    fn compute_hashes_with_options(
+        &self,
+        algorithms: &[calculator::SupportedAlgorithm],
+        options: &calculator::HashOptions,
+    ) -> Result<Vec<String>, String>;
+
+    fn compute_hash_with_options(
+        &self,
+        algorithm: calculator::SupportedAlgorithm,
+        options: &calculator::HashOptions,
+    ) -> Result<String, String> {
+        let mut hashes = self.compute_hashes_with_options(&[algorithm], options)?;
+        Ok(hashes.remove(0))
+    }
+
+    fn compute_hash(&self, algorithm: calculator::SupportedAlgorithm) -> Result<String, String> {
+        self.compute_hash_with_options(algorithm, &calculator::HashOptions::default())
+    }
+
+    // Length of the data in bytes, None when it is only known by reading it (standard input).
+    fn data_size(&self) -> Result<Option<u64>, String>;
 }
 
-fn compute_hash_from_reader<R: BufRead>(
+fn compute_hashes_from_reader<R: BufRead>(
     reader: R,
-    algorithm: calculator::SupportedAlgorithm,
-) -> Result<String, String> {
-    calculator::hash_calculator(reader, algorithm)
-        .map_err(|error| format!("Error: Error calculating hash: {}", error))
+    algorithms: &[calculator::SupportedAlgorithm],
+    options: &calculator::HashOptions,
+) -> Result<Vec<String>, String> {
+    let hashes = match algorithms {
+        [algorithm] => calculator::hash_calculator_with_options(reader, *algorithm, options)
+            .map(|hash| vec![hash]),
+        _ => calculator::hash_calculator_multi_with_options(reader, algorithms, options)
+            .map(|hashes| hashes.into_iter().map(|(_, hash)| hash).collect()),
+    };
+    hashes.map_err(|error| format!("Error: Error calculating hash: {}", error))
 }
 
 impl ComputeHash for Data {
-    fn compute_hash(&self, algorithm: calculator::SupportedAlgorithm) -> Result<String, String> {
+    fn compute_hashes_with_options(
+        &self,
+        algorithms: &[calculator::SupportedAlgorithm],
+        options: &calculator::HashOptions,
+    ) -> Result<Vec<String>, String> {
+        self.read_with(input::IoStrategy::Auto)
+            .compute_hashes_with_options(algorithms, options)
+    }
+
+    fn data_size(&self) -> Result<Option<u64>, String> {
         match self {
-            Data::ReadFile(path) if path == "-" => {
-                compute_hash_from_reader(stdin().lock(), algorithm)
-            }
+            Data::ReadFile(path) if path == "-" => Ok(None),
+            Data::ReadFile(path) => fs::metadata(path)
+                .map(|metadata| Some(metadata.len()))
+                .map_err(|error| format!("Error: Cannot open file {}: {}", path, error)),
+            Data::Text(text) => Ok(Some(text.len() as u64)),
+            Data::LinkTarget(path) => fs::read_link(path)
+                .map(|target| Some(target.as_os_str().len() as u64))
+                .map_err(|error| format!("Error: Cannot read link {}: {}", path, error)),
+            Data::Tree(..) => Ok(None),
+        }
+    }
+}
+
+// Data read with a chosen I/O strategy, Data itself is read with IoStrategy::Auto.
+pub struct ReadWith<'a> {
+    data: &'a Data,
+    io: input::IoStrategy,
+}
+
+impl Data {
+    pub fn read_with(&self, io: input::IoStrategy) -> ReadWith<'_> {
+        ReadWith { data: self, io }
+    }
+}
+
+impl ComputeHash for ReadWith<'_> {
+    fn compute_hashes_with_options(
+        &self,
+        algorithms: &[calculator::SupportedAlgorithm],
+        options: &calculator::HashOptions,
+    ) -> Result<Vec<String>, String> {
+        match self.data {
+            Data::ReadFile(path) if path == "-" => input::read_stdin(self.io, |reader| {
+                compute_hashes_from_reader(reader, algorithms, options)
+            }),
             Data::ReadFile(path) => {
                 let file = File::open(path)
                     .map_err(|error| format!("Error: Cannot open file {}: {}", path, error))?;
-                compute_hash_from_reader(BufReader::new(file), algorithm)
+                input::read_file(file, self.io, |reader| {
+                    compute_hashes_from_reader(reader, algorithms, options)
+                })
+                .map_err(|error| format!("Error: Cannot read file {}: {}", path, error))?
             }
             Data::Text(text) => {
-                compute_hash_from_reader(BufReader::new(text.as_bytes()), algorithm)
+                compute_hashes_from_reader(BufReader::new(text.as_bytes()), algorithms, options)
+            }
+            Data::LinkTarget(path) => {
+                let target = fs::read_link(path)
+                    .map_err(|error| format!("Error: Cannot read link {}: {}", path, error))?;
+                compute_hashes_from_reader(
+                    target.as_os_str().as_encoded_bytes(),
+                    algorithms,
+                    options,
+                )
+            }
+            Data::Tree(path, walk_options) => {
+                tree::tree_hashes(path, walk_options, algorithms, options, self.io)
             }
         }
     }
+
+    fn data_size(&self) -> Result<Option<u64>, String> {
+        self.data.data_size()
+    }
+}
+
+impl ComputeHash for [u8] {
+    fn compute_hashes_with_options(
+        &self,
+        algorithms: &[calculator::SupportedAlgorithm],
+        options: &calculator::HashOptions,
+    ) -> Result<Vec<String>, String> {
+        compute_hashes_from_reader(self, algorithms, options)
+    }
+
+    fn data_size(&self) -> Result<Option<u64>, String> {
+        Ok(Some(self.len() as u64))
+    }
 }
 
 fn validate_hash_for_algorithm(
     hash: &str,
     algorithm: calculator::SupportedAlgorithm,
 ) -> Result<(), String> {
+    if let Some(bits) = algorithm.output_bits() {
+        return if extra::is_hash_of_bits(hash, bits) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Error: Hash does not match algorithm {}.",
+                algorithm
+            ))
+        };
+    }
+
     let detected_algorithms =
         extra::detect_hash_algorithm(hash).map_err(|error| format!("{} {}", error, hash))?;
 
@@ -172,15 +524,47 @@ fn validate_hash_for_algorithm(
     }
 }
 
-fn parse_hash_input<S: AsRef<str>>(
-    hash_input: S,
-) -> Result<(Option<calculator::SupportedAlgorithm>, String), String> {
+// Parameters after `@` in a prefix such as `xxh64@seed=42:...` or `xxh3@seed=0x2a,secret=<hex>:...`.
+fn parse_algorithm_params(params: &str) -> Result<calculator::HashOptions, String> {
+    let mut options = calculator::HashOptions::default();
+
+    for param in params.split(',') {
+        match param.split_once('=') {
+            Some((name, value)) if name.trim().eq_ignore_ascii_case("seed") => {
+                options.seed = Some(extra::parse_seed(value)?);
+            }
+            Some((name, value)) if name.trim().eq_ignore_ascii_case("secret") => {
+                options.secret = Some(extra::hex_to_bytes(value)?.into());
+            }
+            _ => {
+                return Err(format!(
+                    "Error: Invalid algorithm parameter: {}",
+                    param.trim()
+                ))
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+type ParsedHashInput = (
+    Option<calculator::SupportedAlgorithm>,
+    String,
+    calculator::HashOptions,
+);
+
+fn parse_hash_input<S: AsRef<str>>(hash_input: S) -> Result<ParsedHashInput, String> {
     let hash_input = hash_input.as_ref().trim();
     if hash_input.is_empty() {
         return Err(String::from("Error: Invalid hash."));
     }
 
     if let Some((algorithm_name, hash)) = hash_input.split_once(':') {
+        let (algorithm_name, options) = match algorithm_name.split_once('@') {
+            Some((algorithm_name, params)) => (algorithm_name, parse_algorithm_params(params)?),
+            None => (algorithm_name, calculator::HashOptions::default()),
+        };
         let algorithm_name = algorithm_name.trim();
         let hash = hash.trim();
 
@@ -190,9 +574,13 @@ fn parse_hash_input<S: AsRef<str>>(
 
         let algorithm = calculator::SupportedAlgorithm::from_input(algorithm_name)?;
         validate_hash_for_algorithm(hash, algorithm)?;
-        Ok((Some(algorithm), hash.to_string()))
+        Ok((Some(algorithm), hash.to_string(), options))
     } else {
-        Ok((None, hash_input.to_string()))
+        Ok((
+            None,
+            hash_input.to_string(),
+            calculator::HashOptions::default(),
+        ))
     }
 }
 
@@ -200,7 +588,7 @@ pub fn resolve_hash_input<S: AsRef<str>>(
     hash_input: S,
     algorithm: Option<calculator::SupportedAlgorithm>,
 ) -> Result<ResolvedHashInput, String> {
-    let (prefixed_algorithm, hash) = parse_hash_input(hash_input)?;
+    let (prefixed_algorithm, hash, options) = parse_hash_input(hash_input)?;
 
     let algorithms = match (algorithm, prefixed_algorithm) {
         (Some(specified_algorithm), Some(prefixed_algorithm))
@@ -222,6 +610,40 @@ pub fn resolve_hash_input<S: AsRef<str>>(
         hash,
         algorithms,
         detected_from_hash: algorithm.is_none() && prefixed_algorithm.is_none(),
+        options,
+    })
+}
+
+fn merge_option<T: Clone + PartialEq>(
+    name: &str,
+    specified: &Option<T>,
+    prefixed: &Option<T>,
+) -> Result<Option<T>, String> {
+    match (specified, prefixed) {
+        (Some(specified), Some(prefixed)) if specified != prefixed => Err(format!(
+            "Error: Conflicting {}: the specified value differs from the hash prefix.",
+            name
+        )),
+        _ => Ok(specified.clone().or_else(|| prefixed.clone())),
+    }
+}
+
+pub fn merge_hash_options(
+    specified: &calculator::HashOptions,
+    prefixed: &calculator::HashOptions,
+) -> Result<calculator::HashOptions, String> {
+    Ok(calculator::HashOptions {
+        key: merge_option("keys", &specified.key, &prefixed.key)?,
+        derive_key_context: merge_option(
+            "derive-key contexts",
+            &specified.derive_key_context,
+            &prefixed.derive_key_context,
+        )?,
+        seed: merge_option("seeds", &specified.seed, &prefixed.seed)?,
+        secret: merge_option("secrets", &specified.secret, &prefixed.secret)?,
+        hmac: specified.hmac || prefixed.hmac,
+        // Hash prefixes cannot name a backend.
+        backend: specified.backend,
     })
 }
 
@@ -231,7 +653,7 @@ pub fn match_algorithm<S: AsRef<str>>(
     calculator::SupportedAlgorithm::from_input(algorithm)
 }
 
-fn resolve_shasum_entry_path(base_dir: &Path, file_path: &str) -> String {
+pub(crate) fn resolve_shasum_entry_path(base_dir: &Path, file_path: &str) -> String {
     if file_path == "-" {
         return file_path.to_string();
     }
@@ -244,65 +666,246 @@ fn resolve_shasum_entry_path(base_dir: &Path, file_path: &str) -> String {
     }
 }
 
+// Directory relative file names in a shasum file are resolved against.
+pub fn shasum_base_dir(shasum_file_path: &str) -> &Path {
+    Path::new(shasum_file_path)
+        .parent()
+        .filter(|path| !path.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+fn is_decimal_column(column: &str) -> bool {
+    column.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+// GNU separates the hash from the name with "  " (text mode) or " *" (binary mode), md5 -r with " ".
+fn strip_separator(rest: &str) -> Option<&str> {
+    let file_path = rest.strip_prefix([' ', '\t'])?;
+    let file_path = file_path.strip_prefix([' ', '*']).unwrap_or(file_path);
+    (!file_path.is_empty()).then_some(file_path)
+}
+
+/*
+* Everything after the separator is the file name, kept verbatim, spaces included. A line is
+  read as cksum or sum output ("checksum size name") for those algorithms, or without an
+  algorithm when it is not a GNU line: "hash  name" with a hex digest such as an all-digit CRC32
+  stays a GNU line even when the name starts with a number.
+*/
+fn parse_shasum_line(
+    line: &str,
+    algorithm: Option<calculator::SupportedAlgorithm>,
+) -> Result<(String, &str), String> {
+    let invalid = || "Error: Not a valid shasum file.".to_string();
+    let (hash, rest) = line.split_at(line.find([' ', '\t']).ok_or_else(invalid)?);
+
+    let is_gnu_line = (rest.starts_with("  ") || rest.starts_with(" *"))
+        && extra::detect_hex_hash_algorithm(hash).is_some();
+    let has_size_column = match algorithm {
+        Some(algorithm) => algorithm.has_size_column(),
+        None => !is_gnu_line,
+    };
+    if has_size_column && is_decimal_column(hash) {
+        let rest = rest.trim_start();
+        if let Some(index) = rest.find([' ', '\t']) {
+            let (size, rest) = rest.split_at(index);
+            if let (true, Some(file_path)) = (is_decimal_column(size), strip_separator(rest)) {
+                return Ok((format!("{} {}", hash, size), file_path));
+            }
+        }
+    }
+    strip_separator(rest)
+        .map(|file_path| (hash.to_string(), file_path))
+        .ok_or_else(invalid)
+}
+
+/*
+* BSD-style tagged line, `SHA256 (file) = hash`, as `shasum --tag` and `md5` print it. The file
+  name is everything between the first " (" and the last ") = ", so it may contain parentheses.
+*/
+fn parse_tagged_line(line: &str) -> Option<(&str, &str, &str)> {
+    let (tag, rest) = line.split_once(" (")?;
+    let (file_path, hash) = rest.rsplit_once(") = ")?;
+    if tag.is_empty() || tag.contains(char::is_whitespace) || hash.trim().is_empty() {
+        return None;
+    }
+    Some((tag, file_path, hash.trim()))
+}
+
 pub fn phase_shasum_file<S: AsRef<str>>(
     shasum_file_path: S,
     algorithm: Option<calculator::SupportedAlgorithm>,
 ) -> Result<Vec<Compare>, String> {
+    phase_shasum_file_with_options(
+        shasum_file_path,
+        algorithm,
+        &calculator::HashOptions::default(),
+    )
+}
+
+pub fn phase_shasum_file_with_options<S: AsRef<str>>(
+    shasum_file_path: S,
+    algorithm: Option<calculator::SupportedAlgorithm>,
+    options: &calculator::HashOptions,
+) -> Result<Vec<Compare>, String> {
+    phase_shasum_file_with_delimiter(shasum_file_path, algorithm, options, b'\n')
+}
+
+// Records end with `delimiter`, b'\0' for the output of `sha256sum -z`.
+pub fn phase_shasum_file_with_delimiter<S: AsRef<str>>(
+    shasum_file_path: S,
+    algorithm: Option<calculator::SupportedAlgorithm>,
+    options: &calculator::HashOptions,
+    delimiter: u8,
+) -> Result<Vec<Compare>, String> {
+    let shasum_file = read_shasum_file(shasum_file_path, algorithm, options, delimiter)?;
+    match shasum_file.malformed.into_iter().next() {
+        Some((_, error)) => Err(error),
+        None => Ok(shasum_file.tasks),
+    }
+}
+
+// A shasum file read line by line, so the lines that could be parsed are checked regardless.
+pub struct ShasumFile {
+    pub tasks: Vec<Compare>,
+    // Line number and error of every line that is not a valid checksum line.
+    pub malformed: Vec<(usize, String)>,
+}
+
+pub fn read_shasum_file<S: AsRef<str>>(
+    shasum_file_path: S,
+    algorithm: Option<calculator::SupportedAlgorithm>,
+    options: &calculator::HashOptions,
+    delimiter: u8,
+) -> Result<ShasumFile, String> {
     /*
     Example shasum file:
         ee1fb7719c31070f1fbdc8f2d32370c9d1ca6962  image.png
         ee1fb7719c31070f1fbdc8f2d32370c9d1ca6962 *image.png
                                                  ^ In binary mode, neglected.
+    Example cksum / sum -r / sum -s output, checksum and size columns before the file name:
+        2941961746 16 image.png
+        02698     1 image.png
+    Example prefixed hash with algorithm parameters:
+        xxh64@seed=42:bd2770de5166d2f3  滕王阁序.txt
+    Example BSD-style tagged line, may be mixed with the others:
+        SHA1 (image.png) = ee1fb7719c31070f1fbdc8f2d32370c9d1ca6962
+    Example GNU escaped name, the leading backslash marks "\n" and "\\" escapes in the name:
+        \ee1fb7719c31070f1fbdc8f2d32370c9d1ca6962  new\nline.png
+    Lines starting with "#" are comments. The name is everything after the separator, so it may
+    start or end with spaces.
+    A file starting with "%%%% HASHDEEP-1.0" is read as a hashdeep file, see hashdeep.rs.
+    A file ending in .sfv or starting with a ";" comment is read as an SFV file, see sfv.rs.
      */
     let shasum_file_path = shasum_file_path.as_ref();
     let file = File::open(shasum_file_path)
         .map_err(|error| format!("Error: Cannot open file {}: {}", shasum_file_path, error))?;
-    let reader = BufReader::new(file);
-    let base_dir = Path::new(shasum_file_path)
-        .parent()
-        .filter(|path| !path.as_os_str().is_empty())
-        .unwrap_or(Path::new("."));
+    let mut reader = BufReader::new(file);
+    let base_dir = shasum_base_dir(shasum_file_path);
 
-    let mut compare_tasks = Vec::new();
+    let starts_with_header = reader
+        .fill_buf()
+        .map(|buffer| buffer.starts_with(hashdeep::HASHDEEP_HEADER.as_bytes()))
+        .unwrap_or(false);
+    if starts_with_header {
+        let hashdeep_file = hashdeep::HashdeepFile::parse(reader)?;
+        return Ok(ShasumFile {
+            tasks: hashdeep_file.compare_tasks(base_dir, algorithm, options)?,
+            malformed: hashdeep_file.malformed,
+        });
+    }
+    let starts_with_comment = reader
+        .fill_buf()
+        .map(|buffer| buffer.starts_with(&[sfv::SFV_COMMENT as u8]))
+        .unwrap_or(false);
+    if starts_with_comment || sfv::is_sfv_path(Path::new(shasum_file_path)) {
+        let sfv_file = sfv::parse(reader)?;
+        return Ok(ShasumFile {
+            tasks: sfv::compare_tasks(&sfv_file.entries, base_dir, algorithm, options)?,
+            malformed: sfv_file.malformed,
+        });
+    }
 
-    for line in reader.lines() {
-        let line = line.map_err(|error| {
-            format!(
-                "Error: Cannot read shasum file {}: {}",
-                shasum_file_path, error
-            )
-        })?;
-        let parts: Vec<&str> = line.split_whitespace().collect();
+    let mut shasum_file = ShasumFile {
+        tasks: Vec::new(),
+        malformed: Vec::new(),
+    };
+
+    for (index, record) in reader.split(delimiter).enumerate() {
+        let record = record
+            .map_err(|error| error.to_string())
+            .and_then(|record| String::from_utf8(record).map_err(|error| error.to_string()))
+            .map_err(|error| {
+                format!(
+                    "Error: Cannot read shasum file {}: {}",
+                    shasum_file_path, error
+                )
+            })?;
+        let line = match delimiter {
+            b'\n' => record.strip_suffix('\r').unwrap_or(&record),
+            _ => &record,
+        };
+        let line = line.trim_start();
 
-        if parts.is_empty() {
+        if line.trim_end().is_empty() || line.starts_with('#') {
             continue;
         }
-
-        if parts.len() != 2 {
-            return Err("Error: Not a valid shasum file.".to_string());
+        match parse_shasum_record(line, base_dir, algorithm, options) {
+            Ok(task) => shasum_file.tasks.push(task),
+            Err(error) => shasum_file.malformed.push((index + 1, error)),
         }
+    }
 
-        let resolved_hash = resolve_hash_input(parts[0], algorithm)?;
-        let file_path = parts[1].strip_prefix('*').unwrap_or(parts[1]);
-        let file_path = resolve_shasum_entry_path(base_dir, file_path);
+    Ok(shasum_file)
+}
 
-        for algorithm in resolved_hash.algorithms {
-            compare_tasks.push(Compare::new(
-                Data::ReadFile(file_path.clone()),
-                resolved_hash.hash.clone(),
-                algorithm,
-            ));
+fn parse_shasum_record(
+    line: &str,
+    base_dir: &Path,
+    algorithm: Option<calculator::SupportedAlgorithm>,
+    options: &calculator::HashOptions,
+) -> Result<Compare, String> {
+    let (line, escaped) = match line.strip_prefix('\\') {
+        Some(line) => (line, true),
+        None => (line, false),
+    };
+
+    let (hash, file_path, algorithm) = match parse_tagged_line(line) {
+        // The tag names the algorithm of its line.
+        Some((tag, file_path, hash)) => (
+            hash.to_string(),
+            file_path,
+            Some(calculator::SupportedAlgorithm::from_input(tag)?),
+        ),
+        None => {
+            let (hash, file_path) = parse_shasum_line(line, algorithm)?;
+            (hash, file_path, algorithm)
         }
-    }
+    };
+    let file_path = match escaped {
+        true => extra::unescape_file_name(file_path)?,
+        false => file_path.to_string(),
+    };
+
+    let resolved_hash = resolve_hash_input(hash, algorithm)?;
+    let options = merge_hash_options(options, &resolved_hash.options)?;
+    let file_path = resolve_shasum_entry_path(base_dir, &file_path);
 
-    Ok(compare_tasks)
+    Ok(Compare::with_candidates(
+        Data::ReadFile(file_path),
+        resolved_hash.hash,
+        resolved_hash.algorithms,
+        options,
+    ))
 }
 
 #[cfg(test)]
 mod test_core {
-    use super::{match_algorithm, phase_shasum_file, resolve_hash_input, Calculate, Compare, Data};
-    use crate::calculator;
+    use super::{
+        match_algorithm, merge_hash_options, phase_shasum_file, phase_shasum_file_with_options,
+        read_shasum_file, resolve_hash_input, Calculate, CalculateMulti, Compare, Data,
+    };
     use crate::IfMatch::{Failed, Match};
+    use crate::{calculator, extra};
 
     #[test]
     fn test_calculate_compute_hash_file() {
@@ -328,6 +931,52 @@ mod test_core {
         );
     }
 
+    #[test]
+    fn test_calculate_compute_hash_with_encoding() {
+        let task = Calculate::with_encoding(
+            Data::Text(String::from("Veni, vidi, vici")),
+            calculator::SupportedAlgorithm::SHA256,
+            calculator::HashOptions::default(),
+            extra::OutputEncoding::Base64,
+        );
+        assert_eq!(
+            task.compute().unwrap(),
+            "sWEChMlLv5qngzPlfdziNKXoRdYeCc6Rp+Gfokc39GY="
+        );
+    }
+
+    #[test]
+    fn test_calculate_multi_compute_hashes() {
+        let task = CalculateMulti::new(
+            Data::Text(String::from("Veni, vidi, vici")),
+            vec![
+                calculator::SupportedAlgorithm::SHA256,
+                calculator::SupportedAlgorithm::CRC32,
+            ],
+        );
+        let results = task.compute().unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0],
+            (
+                calculator::SupportedAlgorithm::SHA256,
+                String::from("b1610284c94bbf9aa78333e57ddce234a5e845d61e09ce91a7e19fa24737f466")
+            )
+        );
+        assert_eq!(results[1].0, calculator::SupportedAlgorithm::CRC32);
+    }
+
+    #[test]
+    fn test_compare_hash_text_in_base64() {
+        let task = Compare::new(
+            Data::Text(String::from("Veni, vidi, vici")),
+            String::from("sWEChMlLv5qngzPlfdziNKXoRdYeCc6Rp+Gfokc39GY="),
+            calculator::SupportedAlgorithm::SHA256,
+        );
+        assert_eq!(task.compute().unwrap(), Match(String::new()))
+    }
+
     #[test]
     fn test_compare_hash_file() {
         let task = Compare::new(
@@ -358,6 +1007,31 @@ mod test_core {
         assert_eq!(task.compute().unwrap(), Match(String::new()))
     }
 
+    #[test]
+    fn test_compare_hash_with_candidates() {
+        let candidates = extra::detect_hash_algorithm("b1610284c94bbf9aa78333e57ddce234").unwrap();
+        let task = Compare::with_candidates(
+            Data::Text(String::from("Veni, vidi, vici")),
+            String::from("b1610284c94bbf9aa78333e57ddce234"),
+            candidates,
+            calculator::HashOptions::default(),
+        );
+        assert_eq!(task.compute().unwrap(), Failed(String::new()));
+
+        let candidates = extra::detect_hash_algorithm(
+            "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23",
+        )
+        .unwrap();
+        assert_ne!(candidates[0], calculator::SupportedAlgorithm::SHA512_256);
+        let task = Compare::with_candidates(
+            Data::Text(String::from("abc")),
+            String::from("53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23"),
+            candidates,
+            calculator::HashOptions::default(),
+        );
+        assert_eq!(task.compute().unwrap(), Match(String::new()));
+    }
+
     #[test]
     fn test_phase_shasum_file_resolves_relative_paths() {
         let tasks = phase_shasum_file(
@@ -382,6 +1056,122 @@ mod test_core {
         }
     }
 
+    #[test]
+    fn test_phase_shasum_file_supports_mixed_tagged_lines() {
+        let dir = std::env::temp_dir().join(format!("ezcheck-tagged-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a (1).txt"), "Hello").unwrap();
+        let sha256 = "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969";
+        let blake3 = "fbc2b0516ee8744d293b980779178a3508850fdcfe965985782c39601b65794f";
+        let check_file = dir.join("CHECKSUMS");
+        std::fs::write(
+            &check_file,
+            format!(
+                "SHA256 (a (1).txt) = {sha256}\n{sha256}  a (1).txt\nBLAKE3 (a (1).txt) = {blake3}\n"
+            ),
+        )
+        .unwrap();
+
+        let tasks = phase_shasum_file(check_file.to_str().unwrap(), None).unwrap();
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(
+            tasks[2].algorithms(),
+            [calculator::SupportedAlgorithm::BLAKE3]
+        );
+        for task in tasks {
+            assert_eq!(task.compute().unwrap(), Match(String::new()));
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // Windows does not allow line breaks and backslashes in file names.
+    #[cfg(unix)]
+    #[test]
+    fn test_phase_shasum_file_reads_gnu_escapes_and_comments() {
+        let dir = std::env::temp_dir().join(format!("ezcheck-gnu-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let names = ["two  spaces .txt", "new\nline.txt", "back\\slash.txt"];
+        for name in names {
+            std::fs::write(dir.join(name), "Hello").unwrap();
+        }
+        let sha256 = "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969";
+        let check_file = dir.join("SHA256SUMS");
+        std::fs::write(
+            &check_file,
+            format!(
+                "# sha256sum output\n{sha256} *two  spaces .txt\n\\{sha256}  new\\nline.txt\r\n\\SHA256 (back\\\\slash.txt) = {sha256}\n"
+            ),
+        )
+        .unwrap();
+
+        let tasks = phase_shasum_file(check_file.to_str().unwrap(), None).unwrap();
+        assert_eq!(tasks.len(), names.len());
+        for (task, name) in tasks.iter().zip(names) {
+            assert_eq!(task.data.to_string(), dir.join(name).to_str().unwrap());
+            assert_eq!(task.compute().unwrap(), Match(String::new()));
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_shasum_file_keeps_valid_lines_around_malformed_ones() {
+        let dir = std::env::temp_dir().join(format!("ezcheck-malformed-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "Hello").unwrap();
+        let sha256 = "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969";
+        let check_file = dir.join("SHA256SUMS");
+        std::fs::write(
+            &check_file,
+            format!("not a checksum line\n{sha256}  a.txt\nTIGER (a.txt) = {sha256}\n"),
+        )
+        .unwrap();
+        let check_file = check_file.to_str().unwrap();
+
+        let shasum_file =
+            read_shasum_file(check_file, None, &calculator::HashOptions::default(), b'\n').unwrap();
+        assert_eq!(shasum_file.tasks.len(), 1);
+        assert_eq!(
+            shasum_file.tasks[0].compute().unwrap(),
+            Match(String::new())
+        );
+        let lines: Vec<usize> = shasum_file
+            .malformed
+            .iter()
+            .map(|(line, _)| *line)
+            .collect();
+        assert_eq!(lines, [1, 3]);
+
+        assert!(phase_shasum_file(check_file, None).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_phase_shasum_file_keeps_digit_hashes_of_names_starting_with_digits() {
+        let dir = std::env::temp_dir().join(format!("ezcheck-digits-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("2024 report.pdf"), "23").unwrap();
+        let check_file = dir.join("CRC32SUMS");
+        std::fs::write(&check_file, "13792798  2024 report.pdf\n").unwrap();
+        let check_file = check_file.to_str().unwrap();
+
+        for algorithm in [None, Some(calculator::SupportedAlgorithm::CRC32)] {
+            let tasks = phase_shasum_file(check_file, algorithm).unwrap();
+            assert_eq!(tasks.len(), 1);
+            assert_eq!(tasks[0].compute().unwrap(), Match(String::new()));
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_phase_shasum_file_supports_cksum_and_sum_output() {
+        let tasks = phase_shasum_file("tests/cksum.txt", None).unwrap();
+
+        assert_eq!(tasks.len(), 3);
+        for task in tasks {
+            assert_eq!(task.compute().unwrap(), Match(String::new()));
+        }
+    }
+
     #[test]
     fn test_resolve_hash_input_supports_prefixed_hashes() {
         let resolved = resolve_hash_input(
@@ -401,6 +1191,74 @@ mod test_core {
         assert!(!resolved.detected_from_hash);
     }
 
+    #[test]
+    fn test_phase_shasum_file_supports_seeded_xxhash() {
+        let tasks = phase_shasum_file("tests/seeded-xxhsum.txt", None).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        for task in tasks {
+            assert_eq!(task.compute().unwrap(), Match(String::new()));
+        }
+    }
+
+    #[test]
+    fn test_phase_shasum_file_with_hmac_options() {
+        let options = calculator::HashOptions {
+            key: Some(b"Jefe".to_vec().into()),
+            hmac: true,
+            ..calculator::HashOptions::default()
+        };
+        let tasks = phase_shasum_file_with_options(
+            "tests/hmac-sha256sum.txt",
+            Some(calculator::SupportedAlgorithm::SHA256),
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].compute().unwrap(), Match(String::new()));
+
+        let tasks = phase_shasum_file(
+            "tests/hmac-sha256sum.txt",
+            Some(calculator::SupportedAlgorithm::SHA256),
+        )
+        .unwrap();
+        assert_eq!(tasks[0].compute().unwrap(), Failed(String::new()));
+    }
+
+    #[test]
+    fn test_resolve_hash_input_supports_algorithm_params() {
+        let resolved = resolve_hash_input("xxh64@seed=42:bd2770de5166d2f3", None).unwrap();
+
+        assert_eq!(
+            resolved.algorithms,
+            vec![calculator::SupportedAlgorithm::XXHASH64]
+        );
+        assert_eq!(resolved.hash, "bd2770de5166d2f3");
+        assert_eq!(resolved.options.seed, Some(42));
+        assert!(resolve_hash_input("xxh64@pepper=42:bd2770de5166d2f3", None).is_err());
+    }
+
+    #[test]
+    fn test_merge_hash_options_rejects_conflicting_seeds() {
+        let specified = calculator::HashOptions {
+            seed: Some(42),
+            ..calculator::HashOptions::default()
+        };
+        let prefixed = calculator::HashOptions {
+            seed: Some(43),
+            ..calculator::HashOptions::default()
+        };
+
+        assert!(merge_hash_options(&specified, &prefixed).is_err());
+        assert_eq!(
+            merge_hash_options(&calculator::HashOptions::default(), &prefixed)
+                .unwrap()
+                .seed,
+            Some(43)
+        );
+    }
+
     #[test]
     fn test_resolve_hash_input_rejects_conflicting_algorithms() {
         assert!(resolve_hash_input(
@@ -410,6 +1268,19 @@ mod test_core {
         .is_err());
     }
 
+    #[cfg(feature = "hashes_backend")]
+    #[test]
+    fn test_resolve_hash_input_supports_shake_output_length() {
+        let resolved =
+            resolve_hash_input("shake256/128:b14ae8a10ed513c3c43583011c483ff0", None).unwrap();
+
+        assert_eq!(
+            resolved.algorithms,
+            vec![calculator::SupportedAlgorithm::SHAKE256(128)]
+        );
+        assert!(resolve_hash_input("shake256/256:b14ae8a10ed513c3c43583011c483ff0", None).is_err());
+    }
+
     #[test]
     fn test_match_algorithm_supports_xxhash() {
         assert_eq!(
@@ -4,61 +4,36 @@
     feature = "mix_backend"
 )))]
 compile_error!("You must enable at least one of the features: 'hashes_backend', 'ring_backend' or 'mix_backend'.");
-#[cfg(any(
-    all(feature = "hashes_backend", feature = "ring_backend"),
-    all(feature = "hashes_backend", feature = "mix_backend"),
-    all(feature = "ring_backend", feature = "mix_backend"),
-    all(
-        feature = "hashes_backend",
-        feature = "ring_backend",
-        feature = "mix_backend"
-    )
-))]
-compile_error!(
-    "Only one of the features `hashes_backend`, `ring_backend`, or `mix_backend` can be enabled at a time."
-);
 
 use clap::{Parser, Subcommand};
-use ezcheck::calculator::SupportedAlgorithm;
+use ezcheck::audit::{audit_shasum_file, AuditReport};
+use ezcheck::calculator::{Backend, HashOptions, SupportedAlgorithm, ALL_ALGORITHMS};
+use ezcheck::extra::{
+    escape_file_name, hex_to_bytes, parse_seed, strip_xxh3_prefix, OutputEncoding,
+};
+use ezcheck::hashdeep::{HashdeepEntry, HashdeepFile};
+use ezcheck::input::IoStrategy;
+use ezcheck::jobs::{self, JobOptions};
+use ezcheck::sfv::{self, SfvEntry};
+use ezcheck::walk::{walk_directory, SymlinkPolicy, WalkOptions};
 use ezcheck::{
-    match_algorithm, phase_shasum_file, resolve_hash_input, Calculate, Compare, Data, IfMatch,
+    bench, match_algorithm, merge_hash_options, read_shasum_file, resolve_hash_input, selftest,
+    CalculateMulti, Compare, ComputeHash, Data, IfMatch, ShasumFile,
 };
-use std::process;
+use std::path::Path;
+use std::{env, fs, process};
+use zeroize::Zeroizing;
 
 const CLI_ABOUT: &str =
     "An easy tool to calculate and check hash.\nMade with love by Heqi Liu, https://github.com/metaphorme";
 
-#[cfg(feature = "hashes_backend")]
-const CLI_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (Hashes Backend)");
-#[cfg(feature = "ring_backend")]
-const CLI_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (Ring Backend)");
-#[cfg(feature = "mix_backend")]
-const CLI_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (Mix Backend)");
-
-#[cfg(feature = "mix_backend")]
-const CALCULATE_HELP_TEMPLATE: &str = "Calculate hash for a file or text (alias: c)
-
-Usage: {usage}
+#[cfg(all(feature = "ring_backend", feature = "hashes_backend"))]
+const CLI_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (Backends: ring, hashes)");
+#[cfg(all(feature = "ring_backend", not(feature = "hashes_backend")))]
+const CLI_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (Backends: ring)");
+#[cfg(all(feature = "hashes_backend", not(feature = "ring_backend")))]
+const CLI_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (Backends: hashes)");
 
-Arguments:
-  [ALGORITHM]
-    Optional algorithm to use for calculate hash.
-    Supported algorithms:
-      * MD2(Unsafe)
-      * MD4(Unsafe)
-      * MD5(Unsafe)
-      * SHA1(Unsafe)
-      * SHA224
-      * SHA256(default)
-      * SHA384
-      * SHA512
-      * SHA512_256
-      * XXHASH32
-      * XXHASH64
-      * XXHASH3_64
-
-Options:
-{options}";
 #[cfg(feature = "hashes_backend")]
 const CALCULATE_HELP_TEMPLATE: &str = "Calculate hash for a file or text (alias: c)
 
@@ -67,6 +42,7 @@ Usage: {usage}
 Arguments:
   [ALGORITHM]
     Optional algorithm to use for calculate hash.
+    Several comma separated algorithms (e.g. md5,sha256) are computed in one pass.
     Supported algorithms:
       * MD2(Unsafe)
       * MD4(Unsafe)
@@ -77,13 +53,33 @@ Arguments:
       * SHA384
       * SHA512
       * SHA512_256
+      * SHA3_224
+      * SHA3_256
+      * SHA3_384
+      * SHA3_512
+      * SHAKE128[/BITS] (default 256 bits)
+      * SHAKE256[/BITS] (default 512 bits)
+      * BLAKE2B[/BITS] (default 512 bits)
+      * BLAKE2S[/BITS] (default 256 bits)
+      * SM3
+      * STREEBOG256 (GOST R 34.11-2012)
+      * STREEBOG512 (GOST R 34.11-2012)
+      * RIPEMD160
       * XXHASH32
       * XXHASH64
       * XXHASH3_64
+      * XXHASH3_128
+      * BLAKE3
+      * CRC32
+      * CRC32C
+      * CRC64 (CRC-64/XZ)
+      * CKSUM (POSIX cksum)
+      * BSDSUM (sum -r)
+      * SYSVSUM (sum -s)
 
 Options:
 {options}";
-#[cfg(feature = "ring_backend")]
+#[cfg(not(feature = "hashes_backend"))]
 const CALCULATE_HELP_TEMPLATE: &str = "Calculate hash for a file or text (alias: c)
 
 Usage: {usage}
@@ -91,6 +87,7 @@ Usage: {usage}
 Arguments:
   [ALGORITHM]
     Optional algorithm to use for calculate hash.
+    Several comma separated algorithms (e.g. md5,sha256) are computed in one pass.
     Supported algorithms:
       * SHA256(default)
       * SHA384
@@ -99,36 +96,18 @@ Arguments:
       * XXHASH32
       * XXHASH64
       * XXHASH3_64
+      * XXHASH3_128
+      * BLAKE3
+      * CRC32
+      * CRC32C
+      * CRC64 (CRC-64/XZ)
+      * CKSUM (POSIX cksum)
+      * BSDSUM (sum -r)
+      * SYSVSUM (sum -s)
 
 Options:
 {options}";
 
-#[cfg(feature = "mix_backend")]
-const COMPARE_HELP_TEMPLATE: &str = "Compare with given hash (alias: m)
-
-Usage: {usage}
-
-Arguments:
-  [ALGORITHM]
-    Optional algorithm to use for calculate hash.
-    Leave blank to automatically detect the hash algorithm.
-    The value passed to -c/--check-hash may also use algorithm:hash.
-    Supported algorithms:
-      * MD2(Unsafe)
-      * MD4(Unsafe)
-      * MD5(Unsafe)
-      * SHA1(Unsafe)
-      * SHA224
-      * SHA256
-      * SHA384
-      * SHA512
-      * SHA512_256
-      * XXHASH32
-      * XXHASH64
-      * XXHASH3_64
-
-Options:
-{options}";
 #[cfg(feature = "hashes_backend")]
 const COMPARE_HELP_TEMPLATE: &str = "Compare with given hash (alias: m)
 
@@ -139,6 +118,7 @@ Arguments:
     Optional algorithm to use for calculate hash.
     Leave blank to automatically detect the hash algorithm.
     The value passed to -c/--check-hash may also use algorithm:hash.
+    Seeded xxHash values may be given as algorithm@seed=N:hash.
     Supported algorithms:
       * MD2(Unsafe)
       * MD4(Unsafe)
@@ -149,13 +129,33 @@ Arguments:
       * SHA384
       * SHA512
       * SHA512_256
+      * SHA3_224
+      * SHA3_256
+      * SHA3_384
+      * SHA3_512
+      * SHAKE128[/BITS] (default 256 bits)
+      * SHAKE256[/BITS] (default 512 bits)
+      * BLAKE2B[/BITS] (default 512 bits)
+      * BLAKE2S[/BITS] (default 256 bits)
+      * SM3
+      * STREEBOG256 (GOST R 34.11-2012)
+      * STREEBOG512 (GOST R 34.11-2012)
+      * RIPEMD160
       * XXHASH32
       * XXHASH64
       * XXHASH3_64
+      * XXHASH3_128
+      * BLAKE3
+      * CRC32
+      * CRC32C
+      * CRC64 (CRC-64/XZ)
+      * CKSUM (POSIX cksum)
+      * BSDSUM (sum -r)
+      * SYSVSUM (sum -s)
 
 Options:
 {options}";
-#[cfg(feature = "ring_backend")]
+#[cfg(not(feature = "hashes_backend"))]
 const COMPARE_HELP_TEMPLATE: &str = "Compare with given hash (alias: m)
 
 Usage: {usage}
@@ -165,6 +165,7 @@ Arguments:
     Optional algorithm to use for calculate hash.
     Leave blank to automatically detect the hash algorithm.
     The value passed to -c/--check-hash may also use algorithm:hash.
+    Seeded xxHash values may be given as algorithm@seed=N:hash.
     Supported algorithms:
       * SHA256
       * SHA384
@@ -173,36 +174,18 @@ Arguments:
       * XXHASH32
       * XXHASH64
       * XXHASH3_64
+      * XXHASH3_128
+      * BLAKE3
+      * CRC32
+      * CRC32C
+      * CRC64 (CRC-64/XZ)
+      * CKSUM (POSIX cksum)
+      * BSDSUM (sum -r)
+      * SYSVSUM (sum -s)
 
 Options:
 {options}";
 
-#[cfg(feature = "mix_backend")]
-const CHECK_HELP_TEMPLATE: &str = "Check with given shasum file (alias: k)
-
-Usage: {usage}
-
-Arguments:
-  [ALGORITHM]
-    Optional algorithm to use for calculate hash.
-    Leave blank to automatically detect the hash algorithm.
-    The hash column in the check file may also use algorithm:hash.
-    Supported algorithms:
-      * MD2(Unsafe)
-      * MD4(Unsafe)
-      * MD5(Unsafe)
-      * SHA1(Unsafe)
-      * SHA224
-      * SHA256
-      * SHA384
-      * SHA512
-      * SHA512_256
-      * XXHASH32
-      * XXHASH64
-      * XXHASH3_64
-
-Options:
-{options}";
 #[cfg(feature = "hashes_backend")]
 const CHECK_HELP_TEMPLATE: &str = "Check with given shasum file (alias: k)
 
@@ -213,6 +196,7 @@ Arguments:
     Optional algorithm to use for calculate hash.
     Leave blank to automatically detect the hash algorithm.
     The hash column in the check file may also use algorithm:hash.
+    Seeded xxHash values may be given as algorithm@seed=N:hash.
     Supported algorithms:
       * MD2(Unsafe)
       * MD4(Unsafe)
@@ -223,13 +207,33 @@ Arguments:
       * SHA384
       * SHA512
       * SHA512_256
+      * SHA3_224
+      * SHA3_256
+      * SHA3_384
+      * SHA3_512
+      * SHAKE128[/BITS] (default 256 bits)
+      * SHAKE256[/BITS] (default 512 bits)
+      * BLAKE2B[/BITS] (default 512 bits)
+      * BLAKE2S[/BITS] (default 256 bits)
+      * SM3
+      * STREEBOG256 (GOST R 34.11-2012)
+      * STREEBOG512 (GOST R 34.11-2012)
+      * RIPEMD160
       * XXHASH32
       * XXHASH64
       * XXHASH3_64
+      * XXHASH3_128
+      * BLAKE3
+      * CRC32
+      * CRC32C
+      * CRC64 (CRC-64/XZ)
+      * CKSUM (POSIX cksum)
+      * BSDSUM (sum -r)
+      * SYSVSUM (sum -s)
 
 Options:
 {options}";
-#[cfg(feature = "ring_backend")]
+#[cfg(not(feature = "hashes_backend"))]
 const CHECK_HELP_TEMPLATE: &str = "Check with given shasum file (alias: k)
 
 Usage: {usage}
@@ -239,6 +243,7 @@ Arguments:
     Optional algorithm to use for calculate hash.
     Leave blank to automatically detect the hash algorithm.
     The hash column in the check file may also use algorithm:hash.
+    Seeded xxHash values may be given as algorithm@seed=N:hash.
     Supported algorithms:
       * SHA256
       * SHA384
@@ -247,6 +252,14 @@ Arguments:
       * XXHASH32
       * XXHASH64
       * XXHASH3_64
+      * XXHASH3_128
+      * BLAKE3
+      * CRC32
+      * CRC32C
+      * CRC64 (CRC-64/XZ)
+      * CKSUM (POSIX cksum)
+      * BSDSUM (sum -r)
+      * SYSVSUM (sum -s)
 
 Options:
 {options}";
@@ -260,6 +273,176 @@ struct Cli {
     args: Args,
 }
 
+#[derive(clap::Args)]
+struct HashOptionArgs {
+    /// Key for keyed hashing as hex (BLAKE3 takes a 32-byte key, BLAKE2b/BLAKE2s up to 64/32 bytes). Needs --insecure-cli-keys, prefer --key-file or --key-env.
+    #[arg(
+        long,
+        value_name = "HEX",
+        requires = "insecure_cli_keys",
+        conflicts_with_all = ["derive_key", "key_source", "hmac"]
+    )]
+    key: Option<String>,
+
+    /// Read the key from a file, used byte for byte unless --key-hex is given.
+    #[arg(
+        long,
+        value_name = "PATH",
+        group = "key_source",
+        conflicts_with = "derive_key"
+    )]
+    key_file: Option<String>,
+
+    /// Read the key from an environment variable, used byte for byte unless --key-hex is given.
+    #[arg(
+        long,
+        value_name = "VAR",
+        group = "key_source",
+        conflicts_with = "derive_key"
+    )]
+    key_env: Option<String>,
+
+    /// Treat the contents of --key-file or --key-env as hex.
+    #[arg(long, requires = "key_source")]
+    key_hex: bool,
+
+    /// Calculate an HMAC keyed with --key-file or --key-env instead of a plain hash.
+    #[arg(long, requires = "key_source")]
+    hmac: bool,
+
+    /// Context string for BLAKE3 derive-key mode.
+    #[arg(long, value_name = "CONTEXT")]
+    derive_key: Option<String>,
+
+    /// Seed for xxHash algorithms, in decimal or 0x-prefixed hex.
+    #[arg(long, value_name = "SEED")]
+    seed: Option<String>,
+
+    /// Custom XXH3 secret as hex (at least 136 bytes, only used for inputs longer than 240 bytes). Needs --insecure-cli-keys, prefer --secret-file or --secret-env.
+    #[arg(
+        long,
+        value_name = "HEX",
+        requires = "insecure_cli_keys",
+        conflicts_with = "secret_source"
+    )]
+    secret: Option<String>,
+
+    /// Read the XXH3 secret from a file, used byte for byte unless --secret-hex is given.
+    #[arg(long, value_name = "PATH", group = "secret_source")]
+    secret_file: Option<String>,
+
+    /// Read the XXH3 secret from an environment variable, used byte for byte unless --secret-hex is given.
+    #[arg(long, value_name = "VAR", group = "secret_source")]
+    secret_env: Option<String>,
+
+    /// Treat the contents of --secret-file or --secret-env as hex.
+    #[arg(long, requires = "secret_source")]
+    secret_hex: bool,
+
+    /// Allow --key and --secret, whose values are visible to other users in the process list.
+    #[arg(long)]
+    insecure_cli_keys: bool,
+
+    /// Backend for algorithms both ring and hashes implement: auto (default, prefers ring), ring or hashes.
+    #[arg(long, value_name = "BACKEND", env = "EZCHECK_BACKEND")]
+    backend: Option<String>,
+
+    /// How files are read: auto (default, by file size), standard (8 KiB reads), buffered (1 MiB reads), pipelined (1 MiB reads on a reader thread) or mmap. Buffered, pipelined and mmap drop hashed pages from the page cache, auto leaves it alone.
+    #[arg(long, value_name = "STRATEGY")]
+    io: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct JobArgs {
+    /// Number of files to hash concurrently, 0 for one per CPU.
+    #[arg(short, long, value_name = "N", default_value_t = 1)]
+    jobs: usize,
+
+    /// Print results as files finish instead of in input order.
+    #[arg(long)]
+    unordered: bool,
+
+    /// Most files read at once from the same device, useful on spinning disks (Unix only).
+    #[arg(long, value_name = "N")]
+    per_device: Option<usize>,
+}
+
+#[derive(clap::Args)]
+struct OutputArgs {
+    /// Output encoding: hex (default), HEX, base64, base64url, base32 or nix32.
+    #[arg(short, long, value_name = "ENCODING")]
+    encoding: Option<String>,
+
+    /// Print a hashdeep file: a header, then size, hashes and file name per line.
+    #[arg(long, conflicts_with_all = ["encoding", "text"])]
+    hashdeep: bool,
+
+    /// Print BSD-style tagged lines, "SHA256 (file) = hash", as `sha256sum --tag` does.
+    #[arg(long, conflicts_with_all = ["hashdeep", "text", "seed"])]
+    tag: bool,
+
+    /// End each line with NUL instead of a newline and print file names unescaped, as `sha256sum -z` does.
+    #[arg(short, long, conflicts_with = "hashdeep")]
+    zero: bool,
+
+    /// Print an SFV file: a comment, then file name and CRC32 per line. CRC32 is the only algorithm allowed.
+    #[arg(long, conflicts_with_all = ["encoding", "hashdeep", "tag", "text", "zero"])]
+    sfv: bool,
+}
+
+// Switches shared with `sha256sum -c`.
+#[derive(clap::Args)]
+struct CheckArgs {
+    /// Read NUL-terminated lines, as printed by `calculate -z` and `sha256sum -z`.
+    #[arg(short, long)]
+    zero: bool,
+
+    /// Skip files that do not exist instead of failing.
+    #[arg(long, conflicts_with = "audit")]
+    ignore_missing: bool,
+
+    /// Do not print OK for files that match, only failures.
+    #[arg(long)]
+    quiet: bool,
+
+    /// Print nothing, the exit code tells whether every file matched.
+    #[arg(long)]
+    status: bool,
+
+    /// Fail when the check file has improperly formatted lines, after checking the others.
+    #[arg(long)]
+    strict: bool,
+
+    /// Print a warning for every improperly formatted line.
+    #[arg(short, long)]
+    warn: bool,
+}
+
+#[derive(clap::Args)]
+struct WalkFilterArgs {
+    /// Only hash files below directories matching this glob, can be repeated.
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip files and directories matching this glob, can be repeated. Patterns in a .ezcheckignore file in the directory are skipped too.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Symbolic links below directories: skip (default), follow, or target to hash the path stored in the link.
+    #[arg(long, value_name = "POLICY")]
+    symlinks: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct WalkArgs {
+    /// Hash every file below directories given with -f/--file, named relative to the directory.
+    #[arg(short, long)]
+    recursive: bool,
+
+    #[command(flatten)]
+    filters: WalkFilterArgs,
+}
+
 #[derive(Subcommand)]
 enum Args {
     /// Calculate hash for a file or text (alias: c)
@@ -274,6 +457,18 @@ enum Args {
         /// Direct text input for hash calculation.
         #[arg(short, long)]
         text: Option<String>,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        #[command(flatten)]
+        walk_options: WalkArgs,
+
+        #[command(flatten)]
+        hash_options: HashOptionArgs,
+
+        #[command(flatten)]
+        job_options: JobArgs,
     },
 
     /// Compare with given hash (alias: m)
@@ -289,9 +484,15 @@ enum Args {
         #[arg(short, long)]
         text: Option<String>,
 
-        /// Hash to compare with.
+        /// Hash to compare with. For a directory given with -f/--file, its tree hash.
         #[arg(short, long)]
         check_hash: Option<String>,
+
+        #[command(flatten)]
+        tree_options: WalkFilterArgs,
+
+        #[command(flatten)]
+        hash_options: HashOptionArgs,
     },
 
     /// Check with given shasum file (alias: k)
@@ -302,7 +503,92 @@ enum Args {
         /// shasum file to check with.
         #[arg(short, long)]
         check_file: Option<String>,
+
+        /// Symbolic links in the shasum file: follow (default), skip, or target to check the path stored in the link.
+        #[arg(long, value_name = "POLICY")]
+        symlinks: Option<String>,
+
+        /// Also walk the directory of the shasum file and report modified, missing, moved and unlisted files.
+        #[arg(long)]
+        audit: bool,
+
+        #[command(flatten)]
+        check_options: CheckArgs,
+
+        #[command(flatten)]
+        hash_options: HashOptionArgs,
+
+        #[command(flatten)]
+        job_options: JobArgs,
+    },
+
+    /// Measure hashing throughput of every algorithm and backend on synthetic data
+    Bench {
+        /// Algorithms to benchmark, every compiled-in algorithm when omitted.
+        algorithms: Vec<String>,
+
+        /// Size of the synthetic input in bytes.
+        #[arg(short, long, default_value_t = bench::DEFAULT_DATA_SIZE)]
+        size: usize,
+
+        /// Comma separated read buffer sizes in bytes to compare (default: 8192, the built-in BUFFER_SIZE).
+        #[arg(short, long, value_delimiter = ',')]
+        buffer_sizes: Vec<usize>,
+
+        /// Number of runs per measurement, the fastest is reported.
+        #[arg(short, long, default_value_t = bench::DEFAULT_ROUNDS)]
+        rounds: usize,
+
+        /// Also hash a temporary file of --size bytes with each I/O strategy (standard, buffered, pipelined, mmap).
+        #[arg(long)]
+        io: bool,
+
+        /// Output format.
+        #[arg(long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
     },
+
+    /// Calculate one Merkle digest of a whole directory tree (alias: t)
+    #[command(alias = "t")]
+    TreeHash {
+        /// Algorithm to use, SHA256 when omitted. Several comma separated algorithms are computed in one pass.
+        algorithm: Option<String>,
+
+        /// Directory to hash.
+        #[arg(short, long)]
+        directory: String,
+
+        /// Output encoding: hex (default), HEX, base64, base64url, base32 or nix32.
+        #[arg(short, long, value_name = "ENCODING")]
+        encoding: Option<String>,
+
+        #[command(flatten)]
+        tree_options: WalkFilterArgs,
+
+        #[command(flatten)]
+        hash_options: HashOptionArgs,
+    },
+
+    /// Run known-answer tests for every compiled-in algorithm, or for NIST CAVP .rsp files
+    Selftest {
+        /// NIST CAVP response files to run instead of the built-in vectors, such as SHA256ShortMsg.rsp.
+        rsp_files: Vec<String>,
+
+        /// Algorithm of the .rsp files, detected from the file name or header when omitted.
+        #[arg(short, long)]
+        algorithm: Option<String>,
+    },
+}
+
+impl From<JobArgs> for JobOptions {
+    fn from(args: JobArgs) -> Self {
+        Self {
+            jobs: args.jobs,
+            per_device: args.per_device,
+            unordered: args.unordered,
+            io: IoStrategy::Auto,
+        }
+    }
 }
 
 fn detect_algorithm(input: Option<String>) -> Option<SupportedAlgorithm> {
@@ -332,29 +618,324 @@ fn validate_input_source(file_present: bool, text_present: bool, help_command: &
     }
 }
 
-fn calculate(algorithm: Option<String>, file: Option<Vec<String>>, text: Option<String>) {
+fn parse_hex_key(hex: &str) -> Zeroizing<Vec<u8>> {
+    Zeroizing::new(hex_to_bytes(hex).unwrap_or_else(|error| exit_with_error(&error)))
+}
+
+// Key material from --<what>, --<what>-file or --<what>-env, the latter two decoded as hex when asked to.
+fn read_key_material(
+    what: &str,
+    hex: Option<&str>,
+    file: Option<&str>,
+    env_name: Option<&str>,
+    from_hex: bool,
+) -> Option<Zeroizing<Vec<u8>>> {
+    let key = match (file, env_name) {
+        (Some(path), _) => Zeroizing::new(fs::read(path).unwrap_or_else(|error| {
+            exit_with_error(&format!(
+                "Error: Cannot read {} file {}: {}",
+                what, path, error
+            ))
+        })),
+        (None, Some(name)) => {
+            let value = Zeroizing::new(env::var(name).unwrap_or_else(|error| {
+                exit_with_error(&format!(
+                    "Error: Cannot read {} from environment variable {}: {}",
+                    what, name, error
+                ))
+            }));
+            Zeroizing::new(value.as_bytes().to_vec())
+        }
+        (None, None) => return hex.map(parse_hex_key),
+    };
+
+    if !from_hex {
+        return Some(key);
+    }
+
+    match std::str::from_utf8(&key) {
+        Ok(hex) => Some(parse_hex_key(hex)),
+        Err(_) => exit_with_error("Error: Invalid hex string."),
+    }
+}
+
+fn hash_options(args: HashOptionArgs) -> HashOptions {
+    HashOptions {
+        key: read_key_material(
+            "key",
+            args.key.as_deref(),
+            args.key_file.as_deref(),
+            args.key_env.as_deref(),
+            args.key_hex,
+        ),
+        derive_key_context: args.derive_key,
+        seed: args
+            .seed
+            .map(|seed| parse_seed(&seed).unwrap_or_else(|error| exit_with_error(&error))),
+        secret: read_key_material(
+            "secret",
+            args.secret.as_deref(),
+            args.secret_file.as_deref(),
+            args.secret_env.as_deref(),
+            args.secret_hex,
+        ),
+        hmac: args.hmac,
+        backend: args
+            .backend
+            .map(|backend| {
+                Backend::from_input(backend).unwrap_or_else(|error| exit_with_error(&error))
+            })
+            .unwrap_or_default(),
+    }
+}
+
+// --io is given with the hash options but only decides how files are read.
+fn io_strategy(args: &HashOptionArgs) -> IoStrategy {
+    args.io
+        .as_ref()
+        .map(|io| IoStrategy::from_input(io).unwrap_or_else(|error| exit_with_error(&error)))
+        .unwrap_or_default()
+}
+
+fn symlink_policy(symlinks: Option<String>, default: SymlinkPolicy) -> SymlinkPolicy {
+    symlinks
+        .map(|symlinks| {
+            SymlinkPolicy::from_input(symlinks).unwrap_or_else(|error| exit_with_error(&error))
+        })
+        .unwrap_or(default)
+}
+
+fn tree_walk_options(args: WalkFilterArgs) -> WalkOptions {
+    WalkOptions {
+        symlinks: symlink_policy(args.symlinks, SymlinkPolicy::default()),
+        include: args.include,
+        exclude: args.exclude,
+    }
+}
+
+fn walk_options(args: WalkArgs) -> Option<WalkOptions> {
+    let filters = &args.filters;
+    if !args.recursive
+        && (!filters.include.is_empty()
+            || !filters.exclude.is_empty()
+            || filters.symlinks.is_some())
+    {
+        exit_with_error("Error: --include, --exclude and --symlinks require -r/--recursive.");
+    }
+    args.recursive.then(|| tree_walk_options(args.filters))
+}
+
+// Files to hash and the names they are printed with, walking directories when recursive.
+fn file_tasks(files: Vec<String>, walk_options: Option<&WalkOptions>) -> Vec<(String, Data)> {
+    let mut tasks = Vec::new();
+    // Files below a directory are named relative to it, and to the current directory when
+    // several paths are given, so two directories cannot list the same name.
+    let prefix_root = files.len() > 1;
+
+    for file in files {
+        let walk_options = match walk_options {
+            Some(walk_options) if fs::metadata(&file).is_ok_and(|metadata| metadata.is_dir()) => {
+                walk_options
+            }
+            _ => {
+                tasks.push((file.clone(), Data::ReadFile(file)));
+                continue;
+            }
+        };
+
+        match walk_directory(&file, walk_options) {
+            Ok(walk) => {
+                for error in walk.errors {
+                    eprintln!("{}", error);
+                }
+                tasks.extend(walk.files.into_iter().map(|walked| {
+                    let name = if prefix_root {
+                        Path::new(&file).join(&walked.name).display().to_string()
+                    } else {
+                        walked.name
+                    };
+                    (name, walked.data)
+                }));
+            }
+            Err(error) => exit_with_error(&error),
+        }
+    }
+
+    tasks
+}
+
+// The command line with the values of --key and --secret left out, as it is written to manifests.
+fn redacted_args(args: impl Iterator<Item = String>) -> Vec<String> {
+    let mut redact_next = false;
+    args.map(|arg| {
+        if std::mem::take(&mut redact_next) {
+            return String::from("<redacted>");
+        }
+        match arg.split_once('=') {
+            Some((name @ ("--key" | "--secret"), _)) => format!("{}=<redacted>", name),
+            _ => {
+                redact_next = arg == "--key" || arg == "--secret";
+                arg
+            }
+        }
+    })
+    .collect()
+}
+
+fn calculate(
+    algorithm: Option<String>,
+    file: Option<Vec<String>>,
+    text: Option<String>,
+    output: OutputArgs,
+    walk_options: Option<WalkOptions>,
+    options: HashOptions,
+    job_options: JobOptions,
+) {
     validate_input_source(file.is_some(), text.is_some(), "ezcheck calculate --help");
 
-    let algorithm = match detect_algorithm(algorithm) {
-        Some(algorithm) => algorithm,
+    let encoding = match output.encoding.map(OutputEncoding::from_input).transpose() {
+        Ok(encoding) => encoding.unwrap_or_default(),
+        Err(error) => exit_with_error(&error),
+    };
+
+    let algorithms = match algorithm {
+        Some(algorithm) => algorithm
+            .split(',')
+            .map(str::trim)
+            .filter(|algorithm| !algorithm.is_empty())
+            .map(|algorithm| {
+                match_algorithm(algorithm).unwrap_or_else(|error| exit_with_error(&error))
+            })
+            .collect(),
+        None if output.sfv => vec![SupportedAlgorithm::CRC32],
         None => {
-            println!("No algorithm specified. Using SHA256 as the default.");
-            SupportedAlgorithm::SHA256
+            // On stderr, so it does not end up in a manifest written to stdout.
+            eprintln!("No algorithm specified. Using SHA256 as the default.");
+            vec![SupportedAlgorithm::SHA256]
         }
     };
+    if output.sfv && algorithms != [SupportedAlgorithm::CRC32] {
+        exit_with_error("Error: SFV files only list CRC32 checksums.");
+    }
+    let multiple = algorithms.len() > 1;
+
+    /*
+    * A seeded hash is only meaningful together with its seed, and several hashes of the same
+      input need their algorithm name, print both in the prefixed form.
+    */
+    let format_result = |algorithm: SupportedAlgorithm, result: String| match options.seed {
+        Some(seed) => format!(
+            "{}@seed={}:{}",
+            algorithm.prefixed_hash_name(),
+            seed,
+            result
+        ),
+        None if multiple => format!("{}:{}", algorithm.prefixed_hash_name(), result),
+        None => result,
+    };
+
+    let end = if output.zero { '\0' } else { '\n' };
+    /*
+    * As in GNU coreutils, a file name with a backslash or a line break is escaped and its line
+      starts with a backslash. NUL-terminated lines need no escaping.
+    */
+    let escaped_name = |file_path: &String| match escape_file_name(file_path) {
+        Some(escaped) if !output.zero => ("\\", escaped),
+        _ => ("", file_path.clone()),
+    };
+
+    // Printed before the first file, like hashdeep does.
+    let hashdeep = output.hashdeep.then(|| {
+        let directory = env::current_dir()
+            .map(|directory| directory.display().to_string())
+            .unwrap_or_default();
+        let command = redacted_args(env::args()).join(" ");
+        HashdeepFile::with_invocation(true, algorithms.clone(), directory, command)
+    });
+    if let Some(hashdeep) = &hashdeep {
+        print!("{}", hashdeep);
+    }
+    if output.sfv {
+        println!("{}", sfv::header());
+    }
 
     if let Some(files) = file {
-        for file_path in files {
-            let task = Calculate::new(Data::ReadFile(file_path.clone()), algorithm);
-            match task.compute() {
-                Ok(result) => println!("{}  {}", result, file_path),
+        let tasks = file_tasks(files, walk_options.as_ref());
+        jobs::run_file_jobs(
+            &tasks,
+            &job_options,
+            |(_, data)| data,
+            |(_, data), contents| {
+                let task = CalculateMulti::with_encoding(
+                    data.clone(),
+                    algorithms.clone(),
+                    options.clone(),
+                    encoding,
+                );
+                match contents {
+                    Some(contents) => task.compute_from(contents),
+                    None => task.compute_from(&data.read_with(job_options.io)),
+                }
+            },
+            |(file_path, data), result| match result {
+                Ok(results) if hashdeep.is_some() => match data.data_size() {
+                    Ok(Some(size)) => println!(
+                        "{}",
+                        HashdeepEntry {
+                            size: Some(size),
+                            hashes: results.into_iter().map(|(_, hash)| hash).collect(),
+                            file_name: file_path.clone(),
+                        }
+                    ),
+                    Ok(None) => eprintln!("Error: The size of {} is unknown.", file_path),
+                    Err(error) => eprintln!("{}", error),
+                },
+                // An SFV line ends at the line break, no escaping is defined.
+                Ok(_) if output.sfv && file_path.contains(['\n', '\r']) => {
+                    eprintln!("Error: Cannot write {:?} to an SFV file.", file_path)
+                }
+                Ok(mut results) if output.sfv => {
+                    let (_, crc32) = results.remove(0);
+                    println!(
+                        "{}",
+                        SfvEntry {
+                            file_name: file_path.clone(),
+                            crc32,
+                        }
+                    );
+                }
+                Ok(results) if output.tag => {
+                    let (escape, file_path) = escaped_name(file_path);
+                    for (algorithm, result) in results {
+                        let tag = algorithm.tag_name();
+                        // The tag names the algorithm, so XXH3 values go without their prefix.
+                        let result = strip_xxh3_prefix(&result).unwrap_or(&result);
+                        print!("{}{} ({}) = {}{}", escape, tag, file_path, result, end);
+                    }
+                }
+                Ok(results) => {
+                    let (escape, file_path) = escaped_name(file_path);
+                    for (algorithm, result) in results {
+                        if algorithm.has_size_column() && !multiple {
+                            print!("{}{} {}{}", escape, result, file_path, end);
+                        } else {
+                            let result = format_result(algorithm, result);
+                            print!("{}{}  {}{}", escape, result, file_path, end);
+                        }
+                    }
+                }
                 Err(error) => eprintln!("{}", error),
-            }
-        }
+            },
+        );
     } else if let Some(text) = text {
-        let task = Calculate::new(Data::Text(text), algorithm);
+        let task =
+            CalculateMulti::with_encoding(Data::Text(text), algorithms, options.clone(), encoding);
         match task.compute() {
-            Ok(result) => println!("{}", result),
+            Ok(results) => {
+                for (algorithm, result) in results {
+                    print!("{}{}", format_result(algorithm, result), end);
+                }
+            }
             Err(error) => eprintln!("{}", error),
         }
     } else {
@@ -367,6 +948,9 @@ fn compare(
     file: Option<String>,
     text: Option<String>,
     check_hash: Option<String>,
+    tree_options: WalkOptions,
+    options: HashOptions,
+    io: IoStrategy,
 ) {
     validate_input_source(file.is_some(), text.is_some(), "ezcheck compare --help");
 
@@ -379,6 +963,10 @@ fn compare(
         Ok(resolved_hash) => resolved_hash,
         Err(error) => exit_with_error(&error),
     };
+    let options = match merge_hash_options(&options, &resolved_hash.options) {
+        Ok(options) => options,
+        Err(error) => exit_with_error(&error),
+    };
 
     if resolved_hash.detected_from_hash {
         if resolved_hash.algorithms.len() == 1 {
@@ -399,42 +987,36 @@ fn compare(
         }
     }
 
-    let mut matched = false;
-
-    for algorithm in resolved_hash.algorithms {
-        let task = match (&file, &text) {
-            (Some(file_path), None) => Compare::new(
-                Data::ReadFile(file_path.clone()),
-                resolved_hash.hash.clone(),
-                algorithm,
-            ),
-            (None, Some(text)) => Compare::new(
-                Data::Text(text.clone()),
-                resolved_hash.hash.clone(),
-                algorithm,
-            ),
-            _ => unreachable!("input validation guarantees exactly one input source"),
-        };
-
-        match task.compute() {
-            Ok(IfMatch::Match(message)) => {
-                println!("{}", message);
-                matched = true;
-                break;
-            }
-            Ok(IfMatch::Failed(message)) => {
-                println!("{}", message);
-            }
-            Err(error) => eprintln!("{}", error),
+    let data = match (file, text) {
+        (Some(path), None) if fs::metadata(&path).is_ok_and(|metadata| metadata.is_dir()) => {
+            Data::Tree(path, tree_options)
         }
-    }
+        (Some(file_path), None) => Data::ReadFile(file_path),
+        (None, Some(text)) => Data::Text(text),
+        _ => unreachable!("input validation guarantees exactly one input source"),
+    };
+    let task =
+        Compare::with_candidates(data, resolved_hash.hash, resolved_hash.algorithms, options);
 
-    if !matched {
-        process::exit(1);
+    match task.compute_from(&task.data.read_with(io)) {
+        Ok(IfMatch::Match(message)) => println!("{}", message),
+        Ok(IfMatch::Failed(message)) => {
+            println!("{}", message);
+            process::exit(1);
+        }
+        Err(error) => exit_with_error(&error),
     }
 }
 
-fn check(algorithm: Option<String>, check_file: Option<String>) {
+fn check(
+    algorithm: Option<String>,
+    check_file: Option<String>,
+    symlinks: Option<String>,
+    audit: bool,
+    check_options: CheckArgs,
+    options: HashOptions,
+    job_options: JobOptions,
+) {
     let check_file = match check_file {
         Some(check_file) => check_file,
         None => exit_with_error(
@@ -442,52 +1024,288 @@ fn check(algorithm: Option<String>, check_file: Option<String>) {
         ),
     };
 
-    match phase_shasum_file(check_file, detect_algorithm(algorithm)) {
-        Ok(tasks) => {
-            let mut current_task = None;
-            let mut current_task_matched = false;
-            let mut has_unmatched_task = false;
+    let symlinks = symlink_policy(symlinks, SymlinkPolicy::Follow);
+    let report = !check_options.status;
 
-            for task in tasks {
-                let task_key = (task.data.to_string(), task.expected_hash().to_string());
+    let delimiter = if check_options.zero { b'\0' } else { b'\n' };
+    let algorithm = detect_algorithm(algorithm);
+    let ShasumFile {
+        mut tasks,
+        malformed,
+    } = match read_shasum_file(&check_file, algorithm, &options, delimiter) {
+        Ok(shasum_file) => shasum_file,
+        Err(error) => exit_with_error(&error),
+    };
 
-                if current_task.as_ref() != Some(&task_key) {
-                    if current_task.is_some() && !current_task_matched {
-                        has_unmatched_task = true;
-                    }
+    // Like sha256sum, the remaining lines are still checked.
+    if check_options.warn && report {
+        for (line, error) in &malformed {
+            eprintln!("{}: {}: {}", check_file, line, error);
+        }
+    }
+    if tasks.is_empty() && !malformed.is_empty() {
+        exit_with_error(&format!(
+            "Error: No properly formatted checksum lines found in {}.",
+            check_file
+        ));
+    }
+    let has_malformed_lines = check_options.strict && !malformed.is_empty();
+
+    if symlinks != SymlinkPolicy::Follow {
+        tasks.retain_mut(|task| {
+            let Data::ReadFile(path) = &task.data else {
+                return true;
+            };
+            let is_link =
+                fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink());
+            if is_link {
+                task.data = Data::LinkTarget(path.clone());
+            }
+            !is_link || symlinks == SymlinkPolicy::Target
+        });
+    }
 
-                    current_task = Some(task_key.clone());
-                    current_task_matched = false;
-                }
+    if check_options.ignore_missing {
+        let listed = tasks.len();
+        tasks.retain(|task| {
+            let metadata = match &task.data {
+                Data::ReadFile(path) if path != "-" => fs::metadata(path),
+                Data::LinkTarget(path) => fs::symlink_metadata(path),
+                _ => return true,
+            };
+            !matches!(metadata, Err(error) if error.kind() == std::io::ErrorKind::NotFound)
+        });
+        if tasks.is_empty() && listed > 0 {
+            exit_with_error(&format!(
+                "Error: No file listed in {} was found.",
+                check_file
+            ));
+        }
+    }
 
-                if current_task_matched {
-                    continue;
-                }
+    if audit {
+        let walk_options = WalkOptions {
+            symlinks,
+            ..WalkOptions::default()
+        };
+        let audit_report = match audit_shasum_file(&check_file, &tasks, &walk_options, &job_options)
+        {
+            Ok(audit_report) => audit_report,
+            Err(error) => exit_with_error(&error),
+        };
+        // Before the report, which ends with its summary line.
+        if report {
+            print_malformed_summary(malformed.len());
+            print_audit_report(&audit_report, check_options.quiet);
+        }
+        if !audit_report.is_clean() || has_malformed_lines {
+            process::exit(1);
+        }
+        return;
+    }
 
-                match task.compute() {
-                    Ok(IfMatch::Match(message)) => {
-                        current_task_matched = true;
-                        println!("{}: {}", task.data, message);
-                    }
-                    Ok(IfMatch::Failed(message)) => {
-                        println!("{}: {}", task.data, message);
+    let mut has_unmatched_task = false;
+
+    jobs::run_file_jobs(
+        &tasks,
+        &job_options,
+        |task| &task.data,
+        |task, contents| match contents {
+            Some(contents) => task.compute_from(contents),
+            None => task.compute_from(&task.data.read_with(job_options.io)),
+        },
+        |task, result| match result {
+            Ok(IfMatch::Match(message)) => {
+                if report && !check_options.quiet {
+                    println!("{}: {}", task.data, message);
+                }
+            }
+            Ok(IfMatch::Failed(message)) => {
+                has_unmatched_task = true;
+                if report {
+                    for line in message.lines() {
+                        println!("{}: {}", task.data, line);
                     }
-                    Err(error) => eprintln!("{}: {}", task.data, error),
                 }
             }
-
-            if current_task.is_some() && !current_task_matched {
+            Err(error) => {
                 has_unmatched_task = true;
+                if report {
+                    eprintln!("{}: {}", task.data, error);
+                }
             }
+        },
+    );
 
-            if has_unmatched_task {
-                process::exit(1);
+    if report {
+        print_malformed_summary(malformed.len());
+    }
+    if has_unmatched_task || has_malformed_lines {
+        process::exit(1);
+    }
+}
+
+fn print_malformed_summary(count: usize) {
+    match count {
+        0 => {}
+        1 => eprintln!("Warning: 1 line is improperly formatted."),
+        _ => eprintln!("Warning: {} lines are improperly formatted.", count),
+    }
+}
+
+// With `quiet`, only the files that need attention are listed.
+fn print_audit_report(report: &AuditReport, quiet: bool) {
+    if !quiet {
+        for name in &report.matched {
+            println!("matched: {}", name);
+        }
+    }
+    for name in &report.modified {
+        println!("modified: {}", name);
+    }
+    for name in &report.missing {
+        println!("missing: {}", name);
+    }
+    for (from, to) in &report.moved {
+        println!("moved: {} -> {}", from, to);
+    }
+    for name in &report.unlisted {
+        println!("unlisted: {}", name);
+    }
+    for error in &report.errors {
+        eprintln!("{}", error);
+    }
+
+    println!(
+        "{} matched, {} modified, {} missing, {} moved, {} unlisted",
+        report.matched.len(),
+        report.modified.len(),
+        report.missing.len(),
+        report.moved.len(),
+        report.unlisted.len()
+    );
+}
+
+fn tree_hash(
+    algorithm: Option<String>,
+    directory: String,
+    encoding: Option<String>,
+    tree_options: WalkOptions,
+    options: HashOptions,
+    io: IoStrategy,
+) {
+    let encoding = match encoding.map(OutputEncoding::from_input).transpose() {
+        Ok(encoding) => encoding.unwrap_or_default(),
+        Err(error) => exit_with_error(&error),
+    };
+    let algorithms: Vec<SupportedAlgorithm> = match algorithm {
+        Some(algorithm) => algorithm
+            .split(',')
+            .map(str::trim)
+            .filter(|algorithm| !algorithm.is_empty())
+            .map(|algorithm| {
+                match_algorithm(algorithm).unwrap_or_else(|error| exit_with_error(&error))
+            })
+            .collect(),
+        None => vec![SupportedAlgorithm::SHA256],
+    };
+    let multiple = algorithms.len() > 1;
+
+    let data = Data::Tree(directory, tree_options);
+    let task = CalculateMulti::with_encoding(data.clone(), algorithms, options, encoding);
+    match task.compute_from(&data.read_with(io)) {
+        Ok(results) => {
+            for (algorithm, result) in results {
+                if multiple {
+                    println!("{}:{}", algorithm.prefixed_hash_name(), result);
+                } else {
+                    println!("{}", result);
+                }
             }
         }
         Err(error) => exit_with_error(&error),
     }
 }
 
+fn bench(
+    algorithms: Vec<String>,
+    size: usize,
+    buffer_sizes: Vec<usize>,
+    rounds: usize,
+    io: bool,
+    format: &str,
+) {
+    let algorithms = if algorithms.is_empty() {
+        ALL_ALGORITHMS.to_vec()
+    } else {
+        algorithms
+            .iter()
+            .map(|algorithm| {
+                match_algorithm(algorithm).unwrap_or_else(|error| exit_with_error(&error))
+            })
+            .collect()
+    };
+
+    let mut report = match bench::run_benchmarks(&algorithms, size, &buffer_sizes, rounds) {
+        Ok(report) => report,
+        Err(error) => exit_with_error(&error),
+    };
+    if io {
+        match bench::run_io_benchmarks(&algorithms, size, rounds) {
+            Ok(io_results) => report.io_results = io_results,
+            Err(error) => exit_with_error(&error),
+        }
+    }
+
+    if format == "json" {
+        println!("{}", report.to_json());
+    } else {
+        print!("{}", report.to_table());
+    }
+}
+
+fn selftest(rsp_files: Vec<String>, algorithm: Option<String>) {
+    let algorithm = detect_algorithm(algorithm);
+    let vectors = if rsp_files.is_empty() {
+        selftest::builtin_vectors()
+    } else {
+        let mut vectors = Vec::new();
+        for rsp_file in rsp_files {
+            match selftest::parse_cavp_file(rsp_file, algorithm) {
+                Ok(parsed) => vectors.extend(parsed),
+                Err(error) => exit_with_error(&error),
+            }
+        }
+        vectors
+    };
+
+    let mut passed = 0;
+    let mut failed = 0;
+    for vector in vectors {
+        for result in vector.compute() {
+            match result {
+                Ok(IfMatch::Match(message)) => {
+                    passed += 1;
+                    println!("{}: {}", vector.label, message);
+                }
+                Ok(IfMatch::Failed(message)) => {
+                    failed += 1;
+                    println!("{}: {}", vector.label, message);
+                }
+                Err(error) => {
+                    failed += 1;
+                    eprintln!("{}: {}", vector.label, error);
+                }
+            }
+        }
+    }
+
+    println!("{} passed, {} failed", passed, failed);
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
 fn main() {
     let args = Cli::parse();
 
@@ -496,8 +1314,24 @@ fn main() {
             algorithm,
             file,
             text,
+            output,
+            walk_options: walk,
+            hash_options: options,
+            job_options,
         } => {
-            calculate(algorithm, file, text);
+            let job_options = JobOptions {
+                io: io_strategy(&options),
+                ..job_options.into()
+            };
+            calculate(
+                algorithm,
+                file,
+                text,
+                output,
+                walk_options(walk),
+                hash_options(options),
+                job_options,
+            );
         }
 
         Args::Compare {
@@ -505,15 +1339,79 @@ fn main() {
             file,
             text,
             check_hash,
+            tree_options,
+            hash_options: options,
         } => {
-            compare(algorithm, file, text, check_hash);
+            let io = io_strategy(&options);
+            compare(
+                algorithm,
+                file,
+                text,
+                check_hash,
+                tree_walk_options(tree_options),
+                hash_options(options),
+                io,
+            );
         }
 
         Args::Check {
             algorithm,
             check_file,
+            symlinks,
+            audit,
+            check_options,
+            hash_options: options,
+            job_options,
+        } => {
+            let job_options = JobOptions {
+                io: io_strategy(&options),
+                ..job_options.into()
+            };
+            check(
+                algorithm,
+                check_file,
+                symlinks,
+                audit,
+                check_options,
+                hash_options(options),
+                job_options,
+            );
+        }
+
+        Args::Bench {
+            algorithms,
+            size,
+            buffer_sizes,
+            rounds,
+            io,
+            format,
+        } => {
+            bench(algorithms, size, buffer_sizes, rounds, io, &format);
+        }
+
+        Args::TreeHash {
+            algorithm,
+            directory,
+            encoding,
+            tree_options,
+            hash_options: options,
+        } => {
+            let io = io_strategy(&options);
+            tree_hash(
+                algorithm,
+                directory,
+                encoding,
+                tree_walk_options(tree_options),
+                hash_options(options),
+                io,
+            );
+        }
+
+        Args::Selftest {
+            rsp_files,
+            algorithm,
         } => {
-            check(algorithm, check_file);
+            selftest(rsp_files, algorithm);
         }
     }
 }
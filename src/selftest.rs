@@ -0,0 +1,406 @@
+use crate::calculator::{hash_calculator_with_options, Backend, HashOptions, SupportedAlgorithm};
+use crate::extra::{hash_matches, hex_to_bytes};
+use crate::{colorize, IfMatch, ANSI_GREEN, ANSI_RED};
+use std::fs;
+use std::path::Path;
+
+/*
+* Known answers checked by `ezcheck selftest`, so a deployed binary can prove it computes correct
+  digests on the CPU it runs on. Messages are the usual test inputs: "abc" and the two-block
+  messages from FIPS 180-2 for MD/SHA, the GOST R 34.11-2012 example for Streebog, the empty
+  string for xxHash and "123456789" for CRC and sum.
+*/
+const ABC: &[u8] = b"abc";
+const FIPS_448_BITS: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+const FIPS_896_BITS: &[u8] = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+#[cfg(feature = "hashes_backend")]
+const GOST_M1: &[u8] = b"012345678901234567890123456789012345678901234567890123456789012";
+const CHECK_INPUT: &[u8] = b"123456789";
+
+const BUILTIN_VECTORS: &[(SupportedAlgorithm, &[u8], &str)] = &[
+    #[cfg(feature = "hashes_backend")]
+    (SupportedAlgorithm::MD2, ABC, "da853b0d3f88d99b30283a69e6ded6bb"),
+    #[cfg(feature = "hashes_backend")]
+    (SupportedAlgorithm::MD4, ABC, "a448017aaf21d8525fc10ae87aa6729d"),
+    #[cfg(feature = "hashes_backend")]
+    (SupportedAlgorithm::MD5, ABC, "900150983cd24fb0d6963f7d28e17f72"),
+    #[cfg(feature = "hashes_backend")]
+    (SupportedAlgorithm::SHA1, ABC, "a9993e364706816aba3e25717850c26c9cd0d89d"),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::SHA224,
+        ABC,
+        "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::SHA224,
+        FIPS_448_BITS,
+        "75388b16512776cc5dba5da1fd890150b0c6455cb4f58b1952522525",
+    ),
+    (
+        SupportedAlgorithm::SHA256,
+        ABC,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    ),
+    (
+        SupportedAlgorithm::SHA256,
+        FIPS_448_BITS,
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+    ),
+    (
+        SupportedAlgorithm::SHA384,
+        ABC,
+        "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7",
+    ),
+    (
+        SupportedAlgorithm::SHA384,
+        FIPS_896_BITS,
+        "09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039",
+    ),
+    (
+        SupportedAlgorithm::SHA512,
+        ABC,
+        "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+    ),
+    (
+        SupportedAlgorithm::SHA512,
+        FIPS_896_BITS,
+        "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909",
+    ),
+    (
+        SupportedAlgorithm::SHA512_256,
+        ABC,
+        "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23",
+    ),
+    (
+        SupportedAlgorithm::SHA512_256,
+        FIPS_896_BITS,
+        "3928e184fb8690f840da3988121d31be65cb9d3ef83ee6146feac861e19b563a",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::SHA3_224,
+        ABC,
+        "e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::SHA3_256,
+        ABC,
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::SHA3_384,
+        ABC,
+        "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b298d88cea927ac7f539f1edf228376d25",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::SHA3_512,
+        ABC,
+        "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::SHAKE128(256),
+        ABC,
+        "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::SHAKE256(512),
+        ABC,
+        "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e4",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::BLAKE2B(512),
+        ABC,
+        "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::BLAKE2S(256),
+        ABC,
+        "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::SM3,
+        ABC,
+        "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::STREEBOG256,
+        GOST_M1,
+        "9d151eefd8590b89daa6ba6cb74af9275dd051026bb149a452fd84e5e57b5500",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::STREEBOG512,
+        GOST_M1,
+        "1b54d01a4af5b9d5cc3d86d68d285462b19abc2475222f35c085122be4ba1ffa00ad30f8767b3a82384c6574f024c311e2a481332b08ef7f41797891c1646f48",
+    ),
+    #[cfg(feature = "hashes_backend")]
+    (
+        SupportedAlgorithm::RIPEMD160,
+        ABC,
+        "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc",
+    ),
+    (SupportedAlgorithm::XXHASH32, b"", "02cc5d05"),
+    (SupportedAlgorithm::XXHASH64, b"", "ef46db3751d8e999"),
+    (SupportedAlgorithm::XXHASH3_64, b"", "XXH3_2d06800538d394c2"),
+    (
+        SupportedAlgorithm::XXHASH3_128,
+        b"",
+        "XXH3_99aa06d3014798d86001c324468d497f",
+    ),
+    (
+        SupportedAlgorithm::BLAKE3,
+        ABC,
+        "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+    ),
+    (SupportedAlgorithm::CRC32, CHECK_INPUT, "cbf43926"),
+    (SupportedAlgorithm::CRC32C, CHECK_INPUT, "e3069283"),
+    (SupportedAlgorithm::CRC64, CHECK_INPUT, "995dc9bbdf1939fa"),
+    (SupportedAlgorithm::CKSUM, CHECK_INPUT, "930766865 9"),
+    (SupportedAlgorithm::BSDSUM, CHECK_INPUT, "53615     1"),
+    (SupportedAlgorithm::SYSVSUM, CHECK_INPUT, "477 1"),
+];
+
+pub struct KnownAnswer {
+    pub label: String,
+    pub algorithm: SupportedAlgorithm,
+    pub message: Vec<u8>,
+    pub expected: String,
+}
+
+impl KnownAnswer {
+    /*
+    * Runs the vector once for every backend implementing the algorithm, so both ring and
+      hashes are tested when they are compiled in together.
+    */
+    pub fn compute(&self) -> Vec<Result<IfMatch, String>> {
+        let backends = self.algorithm.available_backends();
+        if backends.is_empty() {
+            return vec![self.compute_with_backend(Backend::Auto)];
+        }
+
+        backends
+            .into_iter()
+            .map(|backend| self.compute_with_backend(backend))
+            .collect()
+    }
+
+    pub fn compute_with_backend(&self, backend: Backend) -> Result<IfMatch, String> {
+        let options = HashOptions {
+            backend,
+            ..HashOptions::default()
+        };
+        let name = match backend {
+            Backend::Auto => self.algorithm.to_string(),
+            backend => format!("{} ({})", self.algorithm, backend),
+        };
+
+        let hash_result = hash_calculator_with_options(&self.message[..], self.algorithm, &options)
+            .map_err(|error| format!("Error: Error calculating hash: {}", error))?;
+
+        if hash_matches(self.algorithm, &hash_result, &self.expected) {
+            Ok(IfMatch::Match(colorize(format!("{} OK", name), ANSI_GREEN)))
+        } else {
+            Ok(IfMatch::Failed(format!(
+                "{}  Current Hash:{}",
+                colorize(format!("{} FAILED", name), ANSI_RED),
+                hash_result
+            )))
+        }
+    }
+}
+
+pub fn builtin_vectors() -> Vec<KnownAnswer> {
+    BUILTIN_VECTORS
+        .iter()
+        .map(|&(algorithm, message, expected)| KnownAnswer {
+            label: format!("built-in, {} bytes", message.len()),
+            algorithm,
+            message: message.to_vec(),
+            expected: expected.to_string(),
+        })
+        .collect()
+}
+
+/*
+* Guesses the algorithm of a CAVP response file from its name (SHA256ShortMsg.rsp,
+  SHA3_256LongMsg.rsp, ...) or from its `"SHA-256 ShortMsg" information` header.
+*/
+fn detect_cavp_algorithm(path: &Path, content: &str) -> Option<SupportedAlgorithm> {
+    let file_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let header_name = content
+        .lines()
+        .filter_map(|line| line.trim_start_matches('#').trim().strip_prefix('"'))
+        .find_map(|line| line.split_whitespace().next())
+        .map(|name| name.replacen("SHA-", "SHA", 1));
+
+    [Some(file_name), header_name]
+        .into_iter()
+        .flatten()
+        .find_map(|name| {
+            let name = name
+                .strip_suffix("ShortMsg")
+                .or_else(|| name.strip_suffix("LongMsg"))
+                .unwrap_or(&name);
+            SupportedAlgorithm::from_input(name).ok()
+        })
+}
+
+/*
+* Parses a NIST CAVP response file of the SHA*ShortMsg/LongMsg kind:
+      Len = 8
+      Msg = d3
+      MD = 28969cdfa74a12c82f3bad960b0b000aca2ac329deea5c2328ebc6f2ba9802c1
+  `Len` is in bits, a zero-length message is still written as `Msg = 00`.
+*/
+pub fn parse_cavp_file<S: AsRef<str>>(
+    path: S,
+    algorithm: Option<SupportedAlgorithm>,
+) -> Result<Vec<KnownAnswer>, String> {
+    let path = Path::new(path.as_ref());
+    let content = fs::read_to_string(path)
+        .map_err(|error| format!("Error: Failed to read {}: {}", path.display(), error))?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let algorithm = match algorithm.or_else(|| detect_cavp_algorithm(path, &content)) {
+        Some(algorithm) => algorithm,
+        None => {
+            return Err(format!(
+                "Error: Cannot detect the algorithm of {}, please specify it.",
+                file_name
+            ))
+        }
+    };
+
+    let mut vectors = Vec::new();
+    let mut bits = None;
+    let mut message = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        let invalid = |what: &str| {
+            format!(
+                "Error: Invalid {} at {} line {}.",
+                what,
+                file_name,
+                index + 1
+            )
+        };
+
+        match key.trim() {
+            "Len" => {
+                let length = value
+                    .parse::<usize>()
+                    .map_err(|_| invalid("message length"))?;
+                if length % 8 != 0 {
+                    return Err(format!(
+                        "Error: Bit-oriented messages are not supported ({} line {}).",
+                        file_name,
+                        index + 1
+                    ));
+                }
+                bits = Some(length);
+            }
+            "Msg" => {
+                let bytes = hex_to_bytes(value).map_err(|_| invalid("message"))?;
+                let length = bits.ok_or_else(|| invalid("message without length"))? / 8;
+                if bytes.len() < length {
+                    return Err(invalid("message"));
+                }
+                message = Some(bytes[..length].to_vec());
+            }
+            "MD" => {
+                let (Some(length), Some(message)) = (bits.take(), message.take()) else {
+                    return Err(invalid("digest without message"));
+                };
+                vectors.push(KnownAnswer {
+                    label: format!("{} Len = {}", file_name, length),
+                    algorithm,
+                    message,
+                    expected: value.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if vectors.is_empty() {
+        return Err(format!("Error: No test vectors found in {}.", file_name));
+    }
+    Ok(vectors)
+}
+
+#[cfg(test)]
+mod test_selftest {
+    use super::*;
+    use crate::calculator::ALL_ALGORITHMS;
+
+    #[test]
+    fn test_builtin_vectors_pass() {
+        for vector in builtin_vectors() {
+            for result in vector.compute() {
+                assert_eq!(
+                    result.unwrap(),
+                    IfMatch::Match(String::new()),
+                    "{}",
+                    vector.algorithm
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_builtin_vectors_cover_every_algorithm() {
+        let vectors = builtin_vectors();
+        for algorithm in ALL_ALGORITHMS {
+            assert!(
+                vectors.iter().any(|vector| vector.algorithm == *algorithm),
+                "{}",
+                algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_cavp_file() {
+        let vectors = parse_cavp_file("tests/SHA256ShortMsg.rsp", None).unwrap();
+        assert_eq!(vectors.len(), 4);
+        assert_eq!(vectors[0].algorithm, SupportedAlgorithm::SHA256);
+        assert!(vectors[0].message.is_empty());
+        assert_eq!(vectors[1].message, vec![0xd3]);
+        for vector in vectors {
+            for result in vector.compute() {
+                assert_eq!(result.unwrap(), IfMatch::Match(String::new()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_cavp_file_reports_wrong_digest() {
+        let vectors =
+            parse_cavp_file("tests/SHA256ShortMsg.rsp", Some(SupportedAlgorithm::SHA512)).unwrap();
+        assert_eq!(
+            vectors[0].compute_with_backend(Backend::Auto).unwrap(),
+            IfMatch::Failed(String::new())
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{calculator, resolve_shasum_entry_path, Compare, Data};
+use std::fmt;
+use std::io::BufRead;
+use std::path::Path;
+
+pub const SFV_COMMENT: char = ';';
+
+/*
+* An SFV (Simple File Verification) file:
+      ; Generated by ezcheck v0.1.7
+      滕王阁序.txt 3BEBF399
+      image.jpg 2B6B4CF7
+  The CRC32 is the last column after one space or tab, everything before that is the file name,
+  so names may contain and even start or end with spaces. Lines starting with ";" are comments.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SfvEntry {
+    pub file_name: String,
+    pub crc32: String,
+}
+
+// Files ending in .sfv are read as SFV files whatever their first line is.
+pub fn is_sfv_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("sfv"))
+}
+
+fn parse_line(line: &str) -> Result<SfvEntry, String> {
+    let invalid = || format!("Error: Not a valid SFV line: {}", line);
+    let (file_name, crc32) = line.rsplit_once([' ', '\t']).ok_or_else(invalid)?;
+    if file_name.is_empty()
+        || crc32.len() != 8
+        || !crc32.bytes().all(|byte| byte.is_ascii_hexdigit())
+    {
+        return Err(invalid());
+    }
+
+    Ok(SfvEntry {
+        file_name: file_name.to_string(),
+        crc32: crc32.to_string(),
+    })
+}
+
+// An SFV file read line by line, so the valid lines are checked regardless of the others.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SfvFile {
+    pub entries: Vec<SfvEntry>,
+    // Line number and error of every line that is neither a comment nor a valid entry.
+    pub malformed: Vec<(usize, String)>,
+}
+
+pub fn parse<R: BufRead>(reader: R) -> Result<SfvFile, String> {
+    let mut file = SfvFile::default();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|error| format!("Error: Cannot read SFV file: {}", error))?;
+        // Only the end is trimmed, it holds the CRC32 and a file name may start with a space.
+        let line = line.trim_end();
+        if line.trim_start().is_empty() || line.starts_with(SFV_COMMENT) {
+            continue;
+        }
+        match parse_line(line) {
+            Ok(entry) => file.entries.push(entry),
+            Err(error) => file.malformed.push((index + 1, error)),
+        }
+    }
+    Ok(file)
+}
+
+// One CRC32 Compare per file, relative names are resolved against `base_dir`.
+pub fn compare_tasks(
+    entries: &[SfvEntry],
+    base_dir: &Path,
+    algorithm: Option<calculator::SupportedAlgorithm>,
+    options: &calculator::HashOptions,
+) -> Result<Vec<Compare>, String> {
+    if let Some(algorithm) = algorithm {
+        if algorithm != calculator::SupportedAlgorithm::CRC32 {
+            return Err(format!(
+                "Error: SFV files list CRC32 checksums, not {}.",
+                algorithm
+            ));
+        }
+    }
+
+    Ok(entries
+        .iter()
+        .map(|entry| {
+            Compare::with_candidates(
+                Data::ReadFile(resolve_shasum_entry_path(base_dir, &entry.file_name)),
+                entry.crc32.clone(),
+                vec![calculator::SupportedAlgorithm::CRC32],
+                options.clone(),
+            )
+        })
+        .collect())
+}
+
+// The comment SFV tools start their output with.
+pub fn header() -> String {
+    format!(
+        "{} Generated by ezcheck v{}",
+        SFV_COMMENT,
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+impl fmt::Display for SfvEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.file_name, self.crc32.to_ascii_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod test_sfv {
+    use super::*;
+    use crate::IfMatch;
+
+    #[test]
+    fn test_sfv_parse_skips_comments_and_keeps_spaces() {
+        let entries = parse(
+            "; Generated by cksfv\r\n\n滕王阁序.txt 3bebf399\r\na b.txt\t2B6B4CF7\n".as_bytes(),
+        )
+        .unwrap()
+        .entries;
+
+        assert_eq!(
+            entries,
+            [
+                SfvEntry {
+                    file_name: String::from("滕王阁序.txt"),
+                    crc32: String::from("3bebf399"),
+                },
+                SfvEntry {
+                    file_name: String::from("a b.txt"),
+                    crc32: String::from("2B6B4CF7"),
+                },
+            ]
+        );
+        assert_eq!(entries[0].to_string(), "滕王阁序.txt 3BEBF399");
+    }
+
+    #[test]
+    fn test_sfv_keeps_spaces_around_file_names() {
+        let entry = SfvEntry {
+            file_name: String::from(" lead and trail "),
+            crc32: String::from("71beeff9"),
+        };
+
+        let entries = parse(format!("{}\r\n", entry).as_bytes()).unwrap().entries;
+        assert_eq!(entries[0].file_name, " lead and trail ");
+        assert_eq!(entries[0].crc32, "71BEEFF9");
+    }
+
+    #[test]
+    fn test_sfv_rejects_invalid_lines() {
+        let file = parse(
+            "image.jpg\nimage.jpg 7cc0d6\n滕王阁序.txt 3BEBF399\nimage.jpg 7cc0d6zz\n".as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(file.entries.len(), 1);
+        let lines: Vec<usize> = file.malformed.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, [1, 2, 4]);
+    }
+
+    #[test]
+    fn test_sfv_compare_tasks_check_crc32() {
+        let entries = parse("滕王阁序.txt 3BEBF399\n".as_bytes()).unwrap().entries;
+        let options = calculator::HashOptions::default();
+
+        let tasks = compare_tasks(&entries, Path::new("tests"), None, &options).unwrap();
+        assert_eq!(
+            tasks[0].data.to_string(),
+            Path::new("tests").join("滕王阁序.txt").to_str().unwrap()
+        );
+        assert_eq!(tasks[0].compute().unwrap(), IfMatch::Match(String::new()));
+
+        let sha256 = Some(calculator::SupportedAlgorithm::SHA256);
+        assert!(compare_tasks(&entries, Path::new("tests"), sha256, &options).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::input::IoStrategy;
+use crate::walk::{walk_directory, WalkOptions};
+use crate::{calculator, ComputeHash, Data};
+use std::collections::BTreeMap;
+use std::fs;
+
+/*
+* Tree hash format, version 1. Every file below the directory (as listed by walk_directory) is
+  hashed with the algorithm and its options. Each directory then becomes a node, the header line
+  followed by one entry per file or subdirectory, sorted by the UTF-8 bytes of its name:
+
+      ezcheck-tree-v1\n
+      <mode> <name>\0<digest>\n
+
+  mode is 100644 for a file, 100755 for a file with any execute bit set (Unix), 120000 for a
+  symbolic link hashed by its target path and 040000 for a directory. digest is the algorithm's
+  printed output (lowercase hex, or "checksum size" for cksum and sum) of the file contents, or
+  of the node for a directory. The tree hash is the digest of the root node. Directories without
+  files are left out, so an empty directory tree hashes like an empty root node.
+*/
+pub const TREE_HEADER: &str = "ezcheck-tree-v1\n";
+
+const MODE_FILE: &str = "100644";
+const MODE_EXECUTABLE: &str = "100755";
+const MODE_SYMLINK: &str = "120000";
+const MODE_DIRECTORY: &str = "040000";
+
+enum Entry {
+    File(&'static str, Vec<String>),
+    Directory(BTreeMap<String, Entry>),
+}
+
+#[cfg(unix)]
+fn file_mode(data: &Data) -> Result<&'static str, String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    match data {
+        Data::LinkTarget(_) => Ok(MODE_SYMLINK),
+        Data::ReadFile(path) => {
+            let metadata = fs::metadata(path)
+                .map_err(|error| format!("Error: Cannot open file {}: {}", path, error))?;
+            Ok(if metadata.permissions().mode() & 0o111 != 0 {
+                MODE_EXECUTABLE
+            } else {
+                MODE_FILE
+            })
+        }
+        _ => Ok(MODE_FILE),
+    }
+}
+
+#[cfg(not(unix))]
+fn file_mode(data: &Data) -> Result<&'static str, String> {
+    match data {
+        Data::LinkTarget(_) => Ok(MODE_SYMLINK),
+        _ => Ok(MODE_FILE),
+    }
+}
+
+// One digest per algorithm of every node, children first.
+fn node_digests(
+    entries: &BTreeMap<String, Entry>,
+    algorithms: &[calculator::SupportedAlgorithm],
+    options: &calculator::HashOptions,
+) -> Result<Vec<String>, String> {
+    let mut children = Vec::with_capacity(entries.len());
+    for (name, entry) in entries {
+        let (mode, digests) = match entry {
+            Entry::File(mode, digests) => (*mode, digests.clone()),
+            Entry::Directory(entries) => {
+                (MODE_DIRECTORY, node_digests(entries, algorithms, options)?)
+            }
+        };
+        children.push((name, mode, digests));
+    }
+
+    algorithms
+        .iter()
+        .enumerate()
+        .map(|(index, &algorithm)| {
+            let mut node = TREE_HEADER.as_bytes().to_vec();
+            for (name, mode, digests) in &children {
+                node.extend_from_slice(
+                    format!("{} {}\0{}\n", mode, name, digests[index]).as_bytes(),
+                );
+            }
+            node[..].compute_hash_with_options(algorithm, options)
+        })
+        .collect()
+}
+
+// Tree hashes of the directory `root`, one per algorithm, reading every file once with `io`.
+pub fn tree_hashes<S: AsRef<str>>(
+    root: S,
+    walk_options: &WalkOptions,
+    algorithms: &[calculator::SupportedAlgorithm],
+    options: &calculator::HashOptions,
+    io: IoStrategy,
+) -> Result<Vec<String>, String> {
+    let root = root.as_ref();
+    if !fs::metadata(root).is_ok_and(|metadata| metadata.is_dir()) {
+        return Err(format!("Error: Not a directory: {}", root));
+    }
+
+    // A digest of part of the tree would silently identify the wrong data.
+    let walk = walk_directory(root, walk_options)?;
+    if let Some(error) = walk.errors.into_iter().next() {
+        return Err(error);
+    }
+
+    let mut tree = BTreeMap::new();
+    for file in walk.files {
+        let digests = file
+            .data
+            .read_with(io)
+            .compute_hashes_with_options(algorithms, options)?;
+        let mode = file_mode(&file.data)?;
+
+        let mut components: Vec<&str> = file.name.split('/').collect();
+        let file_name = components.pop().unwrap_or_default().to_string();
+        let mut directory = &mut tree;
+        for component in components {
+            let entry = directory
+                .entry(component.to_string())
+                .or_insert_with(|| Entry::Directory(BTreeMap::new()));
+            directory = match entry {
+                Entry::Directory(entries) => entries,
+                Entry::File(..) => {
+                    unreachable!("a walked path is never both a file and a directory")
+                }
+            };
+        }
+        directory.insert(file_name, Entry::File(mode, digests));
+    }
+
+    node_digests(&tree, algorithms, options)
+}
+
+#[cfg(test)]
+mod test_tree {
+    use super::*;
+    use crate::calculator::SupportedAlgorithm;
+
+    fn temp_tree(name: &str) -> std::path::PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("ezcheck-tree-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("sub/b.txt"), "b").unwrap();
+        root
+    }
+
+    fn sha256(data: &[u8]) -> String {
+        data.compute_hash(SupportedAlgorithm::SHA256).unwrap()
+    }
+
+    #[test]
+    fn test_tree_hash_follows_documented_format() {
+        let root = temp_tree("format");
+        let hashes = tree_hashes(
+            root.to_str().unwrap(),
+            &WalkOptions::default(),
+            &[SupportedAlgorithm::SHA256],
+            &calculator::HashOptions::default(),
+            IoStrategy::Auto,
+        )
+        .unwrap();
+
+        let sub = sha256(format!("{}100644 b.txt\0{}\n", TREE_HEADER, sha256(b"b")).as_bytes());
+        let expected = sha256(
+            format!(
+                "{}100644 a.txt\0{}\n040000 sub\0{}\n",
+                TREE_HEADER,
+                sha256(b"a"),
+                sub
+            )
+            .as_bytes(),
+        );
+        assert_eq!(hashes, [expected]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_tree_hash_changes_with_names_and_contents() {
+        let root = temp_tree("changes");
+        let hash = || {
+            tree_hashes(
+                root.to_str().unwrap(),
+                &WalkOptions::default(),
+                &[SupportedAlgorithm::SHA256, SupportedAlgorithm::BLAKE3],
+                &calculator::HashOptions::default(),
+                IoStrategy::Auto,
+            )
+            .unwrap()
+        };
+
+        let original = hash();
+        assert_eq!(original, hash());
+
+        fs::rename(root.join("a.txt"), root.join("c.txt")).unwrap();
+        let renamed = hash();
+        assert_ne!(renamed[0], original[0]);
+        assert_ne!(renamed[1], original[1]);
+
+        fs::write(root.join("c.txt"), "c").unwrap();
+        assert_ne!(hash(), renamed);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,207 @@
+use io_uring::{opcode, squeue, types, IoUring};
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+// Files up to this size are read whole in one request, larger ones are left to the normal path.
+pub const SMALL_FILE_SIZE: usize = 16 * 1024;
+// Files opened, read and closed per round trip to the kernel.
+const BATCH_SIZE: usize = 256;
+
+/*
+* Reads many small files with io_uring: one submission looks up and opens a batch of files,
+  a second one reads and closes them. Returns None when the kernel has no io_uring (or it is
+  disabled), and None for every file that could not be read whole this way: anything but a
+  regular file of at most SMALL_FILE_SIZE bytes, or a file that failed to open. The caller reads
+  those normally, so their errors are reported as usual.
+*/
+pub fn read_small_files(paths: &[&str]) -> Option<Vec<Option<Vec<u8>>>> {
+    let mut ring = IoUring::new(2 * BATCH_SIZE as u32).ok()?;
+    let mut contents = Vec::with_capacity(paths.len());
+
+    for batch in paths.chunks(BATCH_SIZE) {
+        contents.extend(read_batch(&mut ring, batch));
+    }
+    Some(contents)
+}
+
+// High bits of the user data telling the requests for one file apart.
+const STATX: u64 = 1 << 62;
+const CLOSE: u64 = 1 << 63;
+
+fn read_batch(ring: &mut IoUring, paths: &[&str]) -> Vec<Option<Vec<u8>>> {
+    let names: Vec<Option<CString>> = paths
+        .iter()
+        .map(|path| CString::new(Path::new(path).as_os_str().as_bytes()).ok())
+        .collect();
+    // SAFETY: statx is plain old data, all zeroes is a valid value.
+    let mut stats: Vec<libc::statx> = vec![unsafe { std::mem::zeroed() }; paths.len()];
+
+    // O_NONBLOCK keeps a FIFO from blocking the open, it is skipped by its type anyway.
+    let mut lookups = Vec::with_capacity(2 * paths.len());
+    for (index, (name, stat)) in names.iter().zip(stats.iter_mut()).enumerate() {
+        let Some(name) = name else {
+            continue;
+        };
+        lookups.push(
+            opcode::Statx::new(
+                types::Fd(libc::AT_FDCWD),
+                name.as_ptr(),
+                stat as *mut libc::statx as *mut types::statx,
+            )
+            .mask(libc::STATX_TYPE | libc::STATX_SIZE)
+            .build()
+            .user_data(STATX | index as u64),
+        );
+        lookups.push(
+            opcode::OpenAt::new(types::Fd(libc::AT_FDCWD), name.as_ptr())
+                .flags(libc::O_RDONLY | libc::O_CLOEXEC | libc::O_NONBLOCK)
+                .build()
+                .user_data(index as u64),
+        );
+    }
+
+    let mut sizes: Vec<Option<usize>> = vec![None; paths.len()];
+    let mut fds: Vec<Option<i32>> = vec![None; paths.len()];
+    let (completions, complete) = submit(ring, &lookups);
+    for (user_data, result) in completions {
+        let index = (user_data & !STATX) as usize;
+        if user_data & STATX == 0 {
+            fds[index] = (result >= 0).then_some(result);
+        } else if result >= 0 {
+            let stat = &stats[index];
+            let regular = u32::from(stat.stx_mode) & libc::S_IFMT == libc::S_IFREG;
+            if regular && stat.stx_size <= SMALL_FILE_SIZE as u64 {
+                sizes[index] = Some(stat.stx_size as usize);
+            }
+        }
+    }
+    if !complete {
+        // The kernel may still write to these, they must not be freed.
+        std::mem::forget(names);
+        std::mem::forget(stats);
+        close_all(&fds, &vec![false; paths.len()]);
+        return vec![None; paths.len()];
+    }
+
+    /*
+    * The size comes from the path and may be stale by the time the file is opened, so one byte
+      more is asked for: only a short read of exactly the size shows the file was read whole.
+    */
+    let mut buffers: Vec<Vec<u8>> = sizes
+        .iter()
+        .map(|size| vec![0; size.map_or(0, |size| size + 1)])
+        .collect();
+    let mut reads = Vec::new();
+    for (index, (fd, buffer)) in fds.iter().zip(buffers.iter_mut()).enumerate() {
+        let Some(fd) = *fd else {
+            continue;
+        };
+        if sizes[index].is_some() {
+            // A hard link keeps the close even when the read fails or comes up short.
+            reads.push(
+                opcode::Read::new(types::Fd(fd), buffer.as_mut_ptr(), buffer.len() as u32)
+                    .offset(0)
+                    .build()
+                    .flags(squeue::Flags::IO_HARDLINK)
+                    .user_data(index as u64),
+            );
+        }
+        reads.push(
+            opcode::Close::new(types::Fd(fd))
+                .build()
+                .user_data(CLOSE | index as u64),
+        );
+    }
+
+    let mut read_whole = vec![false; paths.len()];
+    let mut closed = vec![false; paths.len()];
+    let (completions, complete) = submit(ring, &reads);
+    for (user_data, result) in completions {
+        let index = (user_data & !CLOSE) as usize;
+        if user_data & CLOSE != 0 {
+            closed[index] = result >= 0;
+        } else {
+            read_whole[index] = result >= 0 && Some(result as usize) == sizes[index];
+        }
+    }
+    if !complete {
+        std::mem::forget(buffers);
+        close_all(&fds, &closed);
+        return vec![None; paths.len()];
+    }
+    close_all(&fds, &closed);
+
+    buffers
+        .into_iter()
+        .zip(read_whole)
+        .zip(sizes)
+        .map(|((mut buffer, read_whole), size)| {
+            read_whole.then(|| {
+                buffer.truncate(size.unwrap_or(0));
+                buffer
+            })
+        })
+        .collect()
+}
+
+fn close_all(fds: &[Option<i32>], closed: &[bool]) {
+    for (fd, closed) in fds.iter().zip(closed) {
+        if let (Some(fd), false) = (fd, closed) {
+            unsafe {
+                libc::close(*fd);
+            }
+        }
+    }
+}
+
+/*
+* Submits `entries` and waits for them, returning (user data, result) per completion and
+  whether every entry completed. Entries left in flight by an error keep pointing into the
+  caller's buffers.
+*/
+fn submit(ring: &mut IoUring, entries: &[squeue::Entry]) -> (Vec<(u64, i32)>, bool) {
+    let mut completions = Vec::with_capacity(entries.len());
+
+    for chunk in entries.chunks(ring.params().sq_entries() as usize) {
+        // SAFETY: the paths and buffers the entries point to outlive the requests, see above.
+        if unsafe { ring.submission().push_multiple(chunk) }.is_err() {
+            return (completions, false);
+        }
+
+        let mut remaining = chunk.len();
+        while remaining > 0 {
+            match ring.submit_and_wait(1) {
+                Ok(_) => {}
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => return (completions, false),
+            }
+            for entry in ring.completion() {
+                completions.push((entry.user_data(), entry.result()));
+                remaining -= 1;
+            }
+        }
+    }
+    (completions, true)
+}
+
+#[cfg(test)]
+mod test_uring {
+    use super::*;
+
+    #[test]
+    fn test_read_small_files() {
+        let paths = ["tests/滕王阁序.txt", "tests/does-not-exist", "tests"];
+        // Kernels without io_uring (or sandboxes blocking it) fall back to the normal path.
+        let Some(contents) = read_small_files(&paths) else {
+            return;
+        };
+
+        assert_eq!(
+            contents[0].as_deref(),
+            Some(&std::fs::read(paths[0]).unwrap()[..])
+        );
+        assert_eq!(contents[1], None);
+        assert_eq!(contents[2], None);
+    }
+}
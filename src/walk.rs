@@ -0,0 +1,260 @@
+use crate::Data;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path};
+use walkdir::WalkDir;
+
+// Glob patterns in this file at the root of a walked directory are excluded, like --exclude.
+pub const IGNORE_FILE: &str = ".ezcheckignore";
+
+/*
+* What a recursive walk does with symbolic links. Skip leaves them out, Follow hashes what they
+  point to (and walks linked directories), Target hashes the path stored in the link itself, so
+  a link is verified without reading what it points to.
+*/
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    #[default]
+    Skip,
+    Follow,
+    Target,
+}
+
+impl SymlinkPolicy {
+    pub fn from_input<S: AsRef<str>>(input: S) -> Result<SymlinkPolicy, String> {
+        match input.as_ref().trim().to_ascii_lowercase().as_str() {
+            "skip" => Ok(SymlinkPolicy::Skip),
+            "follow" => Ok(SymlinkPolicy::Follow),
+            "target" => Ok(SymlinkPolicy::Target),
+            other => Err(format!(
+                "Error: Unsupported symlink policy: {} (expected skip, follow or target).",
+                other
+            )),
+        }
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            SymlinkPolicy::Skip => "skip",
+            SymlinkPolicy::Follow => "follow",
+            SymlinkPolicy::Target => "target",
+        }
+    }
+}
+
+impl fmt::Display for SymlinkPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WalkOptions {
+    pub symlinks: SymlinkPolicy,
+    // Only files matching one of these are hashed, every file when empty.
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+pub struct WalkedFile {
+    // Path relative to the walked directory, always with `/` separators.
+    pub name: String,
+    pub data: Data,
+}
+
+pub struct Walk {
+    pub files: Vec<WalkedFile>,
+    // Entries that could not be read, the walk carries on without them.
+    pub errors: Vec<String>,
+}
+
+/*
+* Patterns are matched against paths relative to the walked directory. As in .gitignore, a
+  pattern without a `/` matches a name at any depth and a leading `/` anchors it to the root.
+  An excluded directory is not descended into.
+*/
+fn build_globs<S: AsRef<str>>(patterns: &[S]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // A trailing `/` only marks a directory, which is matched the same way.
+        let pattern = pattern.as_ref().trim().trim_end_matches('/');
+        let pattern = match pattern.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if pattern.contains('/') => pattern.to_string(),
+            None => format!("**/{}", pattern),
+        };
+        let glob = Glob::new(&pattern)
+            .map_err(|error| format!("Error: Invalid glob pattern {}: {}", pattern, error))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|error| format!("Error: Invalid glob pattern: {}", error))
+}
+
+fn read_ignore_file(root: &Path) -> Result<Vec<String>, String> {
+    let path = root.join(IGNORE_FILE);
+    match fs::read_to_string(&path) {
+        Ok(content) => Ok(content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(format!("Error: Cannot read {}: {}", path.display(), error)),
+    }
+}
+
+/*
+* Name of `path` relative to `root`, joined with `/`. Paths below "." may be given without the
+  leading "./", as shasum files list them. None when the path is outside `root` or not UTF-8.
+*/
+pub fn relative_name(root: &Path, path: &Path) -> Option<String> {
+    let relative = match path.strip_prefix(root) {
+        Ok(relative) => relative,
+        Err(_) if root == Path::new(".") && path.is_relative() => path,
+        Err(_) => return None,
+    };
+    let components: Option<Vec<&str>> = relative
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .map(|component| component.as_os_str().to_str())
+        .collect();
+    Some(components?.join("/"))
+}
+
+// Every file below `root`, sorted by relative path.
+pub fn walk_directory<S: AsRef<str>>(root: S, options: &WalkOptions) -> Result<Walk, String> {
+    let root = Path::new(root.as_ref());
+    let include = build_globs(&options.include)?;
+    let mut exclude_patterns = read_ignore_file(root)?;
+    exclude_patterns.extend(options.exclude.iter().cloned());
+    let exclude = build_globs(&exclude_patterns)?;
+
+    let relative_name = |path: &Path| relative_name(root, path);
+
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    let entries = WalkDir::new(root)
+        .follow_links(options.symlinks == SymlinkPolicy::Follow)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || relative_name(entry.path()).is_none_or(|name| !exclude.is_match(name))
+        });
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                errors.push(format!("Error: Cannot walk directory: {}", error));
+                continue;
+            }
+        };
+        let file_type = entry.file_type();
+        let is_link = file_type.is_symlink();
+        if file_type.is_dir() || (is_link && options.symlinks == SymlinkPolicy::Skip) {
+            continue;
+        }
+
+        let (Some(name), Some(path)) = (relative_name(entry.path()), entry.path().to_str()) else {
+            errors.push(format!(
+                "Error: Skipping path that is not valid UTF-8: {}",
+                entry.path().display()
+            ));
+            continue;
+        };
+        if !include.is_empty() && !include.is_match(&name) {
+            continue;
+        }
+
+        let data = if is_link {
+            Data::LinkTarget(path.to_string())
+        } else {
+            Data::ReadFile(path.to_string())
+        };
+        files.push(WalkedFile { name, data });
+    }
+
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Walk { files, errors })
+}
+
+#[cfg(test)]
+mod test_walk {
+    use super::*;
+
+    fn temp_tree() -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "ezcheck-walk-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("b/target")).unwrap();
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::write(root.join("b/z.txt"), "z").unwrap();
+        fs::write(root.join("a/y.log"), "y").unwrap();
+        fs::write(root.join("a/x.txt"), "x").unwrap();
+        fs::write(root.join("b/target/out.bin"), "out").unwrap();
+        fs::write(root.join(IGNORE_FILE), "# build output\ntarget/\n").unwrap();
+        root
+    }
+
+    fn names(walk: &Walk) -> Vec<&str> {
+        walk.files.iter().map(|file| file.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_walk_directory_sorts_and_ignores() {
+        let root = temp_tree();
+        let walk = walk_directory(root.to_str().unwrap(), &WalkOptions::default()).unwrap();
+        assert_eq!(names(&walk), [IGNORE_FILE, "a/x.txt", "a/y.log", "b/z.txt"]);
+
+        let options = WalkOptions {
+            include: vec![String::from("*.txt")],
+            exclude: vec![String::from("/b")],
+            ..WalkOptions::default()
+        };
+        let walk = walk_directory(root.to_str().unwrap(), &options).unwrap();
+        assert_eq!(names(&walk), ["a/x.txt"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_directory_symlink_policies() {
+        let root = temp_tree();
+        std::os::unix::fs::symlink("a/x.txt", root.join("link")).unwrap();
+
+        let walk_with = |symlinks| {
+            let options = WalkOptions {
+                symlinks,
+                ..WalkOptions::default()
+            };
+            walk_directory(root.to_str().unwrap(), &options).unwrap()
+        };
+
+        assert!(!names(&walk_with(SymlinkPolicy::Skip)).contains(&"link"));
+
+        let walk = walk_with(SymlinkPolicy::Follow);
+        let link = walk.files.iter().find(|file| file.name == "link").unwrap();
+        assert!(matches!(link.data, Data::ReadFile(_)));
+
+        let walk = walk_with(SymlinkPolicy::Target);
+        let link = walk.files.iter().find(|file| file.name == "link").unwrap();
+        assert!(matches!(link.data, Data::LinkTarget(_)));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_symlink_policy_from_input() {
+        assert_eq!(
+            SymlinkPolicy::from_input("Follow").unwrap(),
+            SymlinkPolicy::Follow
+        );
+        assert!(SymlinkPolicy::from_input("hard").is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -134,3 +134,682 @@ fn check_returns_non_zero_when_any_entry_does_not_match() {
         )
     );
 }
+
+#[test]
+fn calculate_blake3_derive_key_mode() {
+    let output = Command::new(ezcheck_bin())
+        .args([
+            "calculate",
+            "blake3",
+            "-t",
+            "Veni, vidi, vici",
+            "--derive-key",
+            "ezcheck 2024-01-01 test context",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "a1a0eab14d5fbce8bdb93d5f5756cde31ce20e93a31d5e1e11c9b96628037684\n"
+    );
+}
+
+#[test]
+fn calculate_cksum_prints_checksum_and_size_columns() {
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "cksum", "-f", "tests/滕王阁序.txt"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "2093067988 2794 tests/滕王阁序.txt\n"
+    );
+}
+
+#[test]
+fn calculate_with_seed_prints_prefixed_hash_accepted_by_compare() {
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "xxh64", "-t", "abc", "--seed", "42"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "xxhash64@seed=42:13c1d910702770e6\n"
+    );
+
+    let status = Command::new(ezcheck_bin())
+        .args([
+            "compare",
+            "-t",
+            "abc",
+            "-c",
+            "xxhash64@seed=42:13c1d910702770e6",
+        ])
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}
+
+#[test]
+fn check_with_hmac_key_from_environment() {
+    let status = Command::new(ezcheck_bin())
+        .env("EZCHECK_TEST_HMAC_KEY", "Jefe")
+        .args([
+            "check",
+            "sha256",
+            "-c",
+            "tests/hmac-sha256sum.txt",
+            "--hmac",
+            "--key-env",
+            "EZCHECK_TEST_HMAC_KEY",
+        ])
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}
+
+#[test]
+fn hmac_rejects_key_on_command_line() {
+    let output = Command::new(ezcheck_bin())
+        .args([
+            "calculate",
+            "sha256",
+            "-t",
+            "x",
+            "--hmac",
+            "--key",
+            "4a656665",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn backend_from_environment_is_validated() {
+    let output = Command::new(ezcheck_bin())
+        .env("EZCHECK_BACKEND", "openssl")
+        .args(["calculate", "sha256", "-t", "x"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn selftest_passes_builtin_and_cavp_vectors() {
+    let status = Command::new(ezcheck_bin())
+        .arg("selftest")
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success());
+
+    let status = Command::new(ezcheck_bin())
+        .args(["selftest", "tests/SHA256ShortMsg.rsp"])
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success());
+
+    let status = Command::new(ezcheck_bin())
+        .args(["selftest", "tests/SHA256ShortMsg.rsp", "-a", "sha384"])
+        .output()
+        .unwrap()
+        .status;
+    assert!(!status.success());
+}
+
+#[test]
+fn calculate_with_several_algorithms_prints_prefixed_lines() {
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "sha256,crc32", "-t", "abc"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\ncrc32:352441c2\n"
+    );
+}
+
+#[test]
+fn compare_ambiguous_hash_from_stdin_reads_input_once() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    // SHA512_256 of "abc"; SHA256 is tried first for a 64 hex digit hash.
+    let mut child = Command::new(ezcheck_bin())
+        .args([
+            "compare",
+            "-f",
+            "-",
+            "-c",
+            "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"abc").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with("\u{1b}[32mSHA512_256 OK\u{1b}[0m\n"));
+}
+
+#[test]
+fn jobs_keep_input_order_and_exit_codes() {
+    let dir = unique_temp_dir();
+    let mut check_file = String::new();
+    let mut files = Vec::new();
+
+    for index in 0..16 {
+        let name = format!("payload-{}.txt", index);
+        fs::write(dir.join(&name), b"Hello").unwrap();
+        let hash = if index == 7 {
+            "085f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969"
+        } else {
+            "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969"
+        };
+        check_file.push_str(&format!("{}  {}\n", hash, name));
+        files.push(dir.join(&name));
+    }
+    let check_path = dir.join("sha256sum.txt");
+    fs::write(&check_path, check_file).unwrap();
+
+    let output = Command::new(ezcheck_bin())
+        .args(["check", "sha256", "-j", "4", "-c"])
+        .arg(&check_path)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let reported: Vec<&str> = stdout
+        .lines()
+        .map(|line| line.split(": ").next().unwrap())
+        .collect();
+    let expected: Vec<String> = files
+        .iter()
+        .map(|file| file.display().to_string())
+        .collect();
+    assert_eq!(reported, expected);
+
+    let output = Command::new(ezcheck_bin())
+        .args([
+            "calculate",
+            "sha256",
+            "-j",
+            "4",
+            "--unordered",
+            "--per-device",
+            "1",
+            "-f",
+        ])
+        .args(&files)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 16);
+}
+
+#[test]
+fn recursive_calculate_output_is_accepted_by_check() {
+    let dir = unique_temp_dir();
+    let root = dir.join("tree");
+    fs::create_dir_all(root.join("b/build")).unwrap();
+    fs::create_dir_all(root.join("a dir")).unwrap();
+    fs::write(root.join("b/z.txt"), b"Hello").unwrap();
+    fs::write(root.join("b/build/out.o"), b"Hello").unwrap();
+    fs::write(root.join("a dir/with space.txt"), b"Hello").unwrap();
+    fs::write(root.join("a dir/skip.log"), b"Hello").unwrap();
+    fs::write(root.join(".ezcheckignore"), b"build/\n").unwrap();
+
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "sha256", "-r", "--exclude", "*.log", "-f"])
+        .arg(&root)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let hash = "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969";
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_eq!(
+        stdout,
+        format!(
+            "181314065df2f2fdaf920b1a8b5311daa216a2d6489a06ada5b49cc514d89417  .ezcheckignore\n\
+             {hash}  a dir/with space.txt\n\
+             {hash}  b/z.txt\n"
+        )
+    );
+
+    let check_path = root.join("SHA256SUMS");
+    fs::write(&check_path, stdout).unwrap();
+    let output = Command::new(ezcheck_bin())
+        .args(["check", "sha256", "-c"])
+        .arg(&check_path)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 3);
+}
+
+#[test]
+fn recursive_calculate_names_files_by_root_with_several_directories() {
+    let dir = unique_temp_dir();
+    fs::create_dir_all(dir.join("d1")).unwrap();
+    fs::create_dir_all(dir.join("d2")).unwrap();
+    fs::write(dir.join("d1/same.txt"), b"Hello").unwrap();
+    fs::write(dir.join("d2/same.txt"), b"Hello").unwrap();
+
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "sha256", "-r", "-f", "d1", "d2"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let hash = "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969";
+    let d1 = Path::new("d1").join("same.txt");
+    let d2 = Path::new("d2").join("same.txt");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{hash}  {}\n{hash}  {}\n", d1.display(), d2.display())
+    );
+
+    let check_path = dir.join("SHA256SUMS");
+    fs::write(&check_path, &output.stdout).unwrap();
+    let output = Command::new(ezcheck_bin())
+        .args(["check", "sha256", "-c"])
+        .arg(&check_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+fn tree_hash_is_accepted_by_compare_and_audit_reports_changes() {
+    let dir = unique_temp_dir();
+    let root = dir.join("tree");
+    fs::create_dir_all(root.join("sub")).unwrap();
+    fs::write(root.join("a.txt"), b"Hello").unwrap();
+    fs::write(root.join("sub/b.txt"), b"Hello").unwrap();
+
+    let output = Command::new(ezcheck_bin())
+        .args(["tree-hash", "sha256", "-d"])
+        .arg(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let tree_hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let compare = |hash: &str| {
+        Command::new(ezcheck_bin())
+            .args(["compare", "sha256", "-c", hash, "-f"])
+            .arg(&root)
+            .output()
+            .unwrap()
+    };
+    assert!(compare(&tree_hash).status.success());
+    fs::write(root.join("sub/c.txt"), b"Hello").unwrap();
+    assert_eq!(compare(&tree_hash).status.code(), Some(1));
+
+    let hash = "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969";
+    let check_path = root.join("SHA256SUMS");
+    fs::write(
+        &check_path,
+        format!("{hash}  a.txt\n{hash}  old.txt\n{hash}  gone.txt\n"),
+    )
+    .unwrap();
+    fs::remove_file(root.join("sub/c.txt")).unwrap();
+
+    let output = Command::new(ezcheck_bin())
+        .args(["check", "sha256", "--audit", "-c"])
+        .arg(&check_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "matched: a.txt\n\
+         missing: gone.txt\n\
+         moved: old.txt -> sub/b.txt\n\
+         1 matched, 0 modified, 1 missing, 1 moved, 0 unlisted\n"
+    );
+
+    fs::write(
+        &check_path,
+        format!("{hash}  a.txt\n{hash}  old.txt\ngarbage\n"),
+    )
+    .unwrap();
+    let audit = |switch: &str| {
+        Command::new(ezcheck_bin())
+            .args(["check", "sha256", "--audit", switch, "-c"])
+            .arg(&check_path)
+            .output()
+            .unwrap()
+    };
+    let output = audit("--quiet");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "moved: old.txt -> sub/b.txt\n\
+         1 matched, 0 modified, 0 missing, 1 moved, 0 unlisted\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Warning: 1 line is improperly formatted.\n"
+    );
+
+    let output = audit("--status");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn hashdeep_output_is_accepted_by_check() {
+    let dir = unique_temp_dir();
+    fs::write(dir.join("payload.txt"), b"Hello").unwrap();
+
+    let output = Command::new(ezcheck_bin())
+        .args([
+            "calculate",
+            "sha256,blake3",
+            "--hashdeep",
+            "-f",
+            "payload.txt",
+        ])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(stdout
+        .starts_with("%%%% HASHDEEP-1.0\n%%%% size,sha256,blake3,filename\n## Invoked from: "));
+    assert!(stdout.ends_with(
+        "\n5,185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969,\
+         fbc2b0516ee8744d293b980779178a3508850fdcfe965985782c39601b65794f,payload.txt\n"
+    ));
+
+    fs::write(dir.join("manifest.txt"), stdout).unwrap();
+    let check = || {
+        Command::new(ezcheck_bin())
+            .args(["check", "-c"])
+            .arg(dir.join("manifest.txt"))
+            .output()
+            .unwrap()
+    };
+    assert!(check().status.success());
+
+    fs::write(dir.join("payload.txt"), b"Hello!").unwrap();
+    let output = check();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("size FAILED"));
+}
+
+#[test]
+fn hashdeep_output_leaves_out_keys() {
+    let dir = unique_temp_dir();
+    fs::write(dir.join("payload.txt"), b"Hello").unwrap();
+    let key = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "blake3", "--hashdeep", "--key", key])
+        .args(["--insecure-cli-keys", "-f", "payload.txt"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains(key));
+    assert!(stdout.contains(" --key <redacted> --insecure-cli-keys -f payload.txt\n"));
+}
+
+#[test]
+fn keys_and_secrets_on_command_line_need_insecure_flag() {
+    let key = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "blake3", "-t", "x", "--key", key])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let secret = "42".repeat(136);
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "xxh3", "-t", "x", "--secret", &secret])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn secret_from_environment_matches_secret_from_command_line() {
+    let dir = unique_temp_dir();
+    fs::write(dir.join("payload.txt"), vec![b'x'; 1000]).unwrap();
+    let secret = "42".repeat(136);
+
+    let from_argv = Command::new(ezcheck_bin())
+        .args(["calculate", "xxh3", "-f", "payload.txt"])
+        .args(["--secret", &secret, "--insecure-cli-keys"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let from_env = Command::new(ezcheck_bin())
+        .env("XXH3_SECRET", &secret)
+        .args(["calculate", "xxh3", "-f", "payload.txt"])
+        .args(["--secret-env", "XXH3_SECRET", "--secret-hex"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let plain = Command::new(ezcheck_bin())
+        .args(["calculate", "xxh3", "-f", "payload.txt"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    assert!(from_argv.status.success());
+    assert!(from_env.status.success());
+    assert_eq!(from_argv.stdout, from_env.stdout);
+    assert_ne!(from_env.stdout, plain.stdout);
+}
+
+#[test]
+fn default_algorithm_notice_stays_out_of_manifests() {
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "--tag", "-f", "tests/滕王阁序.txt"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("SHA256 (tests/滕王阁序.txt) = "));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "No algorithm specified. Using SHA256 as the default.\n"
+    );
+}
+
+// Windows does not allow line breaks and backslashes in file names.
+#[cfg(unix)]
+#[test]
+fn gnu_escaped_and_nul_terminated_output_is_accepted_by_check() {
+    let dir = unique_temp_dir();
+    let names = [
+        " lead.txt",
+        "trail .txt",
+        "new\nline.txt",
+        "back\\slash.txt",
+    ];
+    for name in names {
+        fs::write(dir.join(name), b"Hello").unwrap();
+    }
+    let hash = "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969";
+
+    let calculate = |zero: bool| {
+        let mut command = Command::new(ezcheck_bin());
+        command.args(["calculate", "sha256", "-f"]).args(names);
+        if zero {
+            command.arg("-z");
+        }
+        let output = command.current_dir(&dir).output().unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&calculate(false)),
+        format!(
+            "{hash}   lead.txt\n{hash}  trail .txt\n\\{hash}  new\\nline.txt\n\\{hash}  back\\\\slash.txt\n"
+        )
+    );
+
+    fs::write(dir.join("SHA256SUMS"), calculate(false)).unwrap();
+    fs::write(dir.join("SHA256SUMS.z"), calculate(true)).unwrap();
+    for args in [
+        ["check", "-c", "SHA256SUMS"],
+        ["check", "-zc", "SHA256SUMS.z"],
+    ] {
+        let output = Command::new(ezcheck_bin())
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", args);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout)
+                .matches("OK")
+                .count(),
+            names.len()
+        );
+    }
+}
+
+#[test]
+fn sfv_output_is_accepted_by_check() {
+    let dir = unique_temp_dir();
+    fs::write(dir.join("a b.txt"), b"Hello").unwrap();
+
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "--sfv", "-f", "a b.txt"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(stdout.starts_with("; Generated by ezcheck v"));
+    assert!(stdout.ends_with("\na b.txt F7D18982\n"));
+
+    // Names are resolved against the directory of the SFV file.
+    fs::write(dir.join("files.sfv"), stdout).unwrap();
+    let check = || {
+        Command::new(ezcheck_bin())
+            .args(["check", "-c"])
+            .arg(dir.join("files.sfv"))
+            .output()
+            .unwrap()
+    };
+    assert!(check().status.success());
+
+    fs::write(dir.join("a b.txt"), b"Hello!").unwrap();
+    assert_eq!(check().status.code(), Some(1));
+}
+
+#[test]
+fn check_supports_coreutils_switches() {
+    let dir = unique_temp_dir();
+    fs::write(dir.join("payload.txt"), b"Hello").unwrap();
+    let hash = "185f8db32271fe25f561a6fc938b2e264306ec304eda518007d1764826381969";
+    fs::write(
+        dir.join("SHA256SUMS"),
+        format!("{hash}  payload.txt\n{hash}  missing.txt\nnot a checksum line\n"),
+    )
+    .unwrap();
+
+    let check = |switches: &[&str]| {
+        Command::new(ezcheck_bin())
+            .args(["check", "-c", "SHA256SUMS"])
+            .args(switches)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+
+    // The malformed line is skipped, the missing file still fails the run.
+    let output = check(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("payload.txt: "));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 line is improperly formatted"));
+
+    let output = check(&["--ignore-missing", "--quiet", "--warn"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("SHA256SUMS: 3: "));
+
+    assert_eq!(
+        check(&["--ignore-missing", "--strict"]).status.code(),
+        Some(1)
+    );
+
+    let output = check(&["--status"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty() && output.stderr.is_empty());
+}
+
+#[test]
+fn tagged_xxh3_output_has_no_value_prefix() {
+    let output = Command::new(ezcheck_bin())
+        .args([
+            "calculate",
+            "xxh3,xxh128",
+            "--tag",
+            "-f",
+            "tests/滕王阁序.txt",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "XXH3 (tests/滕王阁序.txt) = 442b919444cc4847\n\
+         XXH128 (tests/滕王阁序.txt) = f07eed3cf9d691f8442b919444cc4847\n"
+    );
+}
+
+#[test]
+fn digit_only_crc32_of_a_name_starting_with_digits_round_trips() {
+    let dir = unique_temp_dir();
+    fs::write(dir.join("2024 report.pdf"), b"23").unwrap();
+
+    let output = Command::new(ezcheck_bin())
+        .args(["calculate", "crc32", "-f", "2024 report.pdf"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "13792798  2024 report.pdf\n"
+    );
+
+    fs::write(dir.join("CRC32SUMS"), &output.stdout).unwrap();
+    let output = Command::new(ezcheck_bin())
+        .args(["check", "-c", "CRC32SUMS"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}